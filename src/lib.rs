@@ -1,10 +1,15 @@
 use core::fmt;
-use std::{collections::BTreeSet, usize};
+use std::{collections::BTreeSet, ops::RangeInclusive};
 
 use getset::Getters;
-use sea_query::{ColumnDef, Iden, Table, TableCreateStatement};
+use sea_query::{
+    ArrayType, ColumnDef, ColumnType, Expr, ForeignKey, Iden, Index, IndexCreateStatement,
+    InsertStatement, Keyword, MysqlQueryBuilder, OnConflict, PostgresQueryBuilder, Query, Quote,
+    SimpleExpr, SqliteQueryBuilder, Table, TableAlterStatement, TableBuilder, TableCreateStatement,
+    Value,
+};
 use serde::{
-    de::{Unexpected, Visitor},
+    de::{DeserializeSeed, Visitor},
     Deserialize, Serialize,
 };
 use thiserror::Error;
@@ -12,12 +17,82 @@ use thiserror::Error;
 #[cfg(test)]
 mod tests;
 
+/// A runtime identifier, lowercased when written. Only its [Iden::unquoted] form is
+/// raw, every statement writes it quoted (see [QuotedIden]), so reserved words and
+/// quote characters in a name can not change the meaning of the SQL.
 pub struct IdenString(pub String);
 
+/// Postgres truncates identifiers longer than this many bytes (NAMEDATALEN - 1).
+pub const MAX_IDEN_LEN: usize = 63;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdenError {
+    #[error("The identifier can not be empty")]
+    Empty,
+    #[error("The identifier has {0} characters, the maximum is {MAX_IDEN_LEN}")]
+    TooLong(usize),
+    #[error("The identifier {0:?} must match [a-z_][a-z0-9_]*")]
+    InvalidCharacters(String),
+    #[error("The identifier {0:?} can not contain uppercase letters")]
+    Uppercase(String),
+}
+
+/// How uppercase letters in field names are handled when a [Schema] or [LiveSchema]
+/// is deserialized. Every column is created with a lowercased name (see [IdenString]),
+/// so in all policies two names only differing in case are rejected as duplicates.
+///
+/// The [Deserialize] impls use the default policy, to use another one deserialize
+/// through [SchemaSeed] or [LiveSchemaSeed].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierCase {
+    /// Names are lowercased, `DeviceID` becomes `deviceid`
+    #[default]
+    Lower,
+    /// Names containing uppercase letters are rejected
+    RejectUppercase,
+    /// Names are kept as declared, only the column created for them is lowercased
+    Preserve,
+}
+
+impl IdentifierCase {
+    /// Applies the policy to a name, returning the name the field should keep.
+    pub fn apply(&self, name: &str) -> Result<String, IdenError> {
+        match self {
+            IdentifierCase::Lower => Ok(name.to_lowercase()),
+            IdentifierCase::RejectUppercase if name.chars().any(char::is_uppercase) => {
+                Err(IdenError::Uppercase(name.into()))
+            }
+            IdentifierCase::RejectUppercase | IdentifierCase::Preserve => Ok(name.into()),
+        }
+    }
+}
+
 impl IdenString {
     pub fn new(name: String) -> Self {
         Self(name)
     }
+
+    /// Builds an identifier from user input, only accepting names matching
+    /// `[a-z_][a-z0-9_]*` with at most [MAX_IDEN_LEN] characters.
+    /// This should be used for any table or column name coming from a request.
+    pub fn try_new(name: &str) -> Result<IdenString, IdenError> {
+        let mut chars = name.chars();
+        let first = chars.next().ok_or(IdenError::Empty)?;
+
+        let valid_start = first == '_' || first.is_ascii_lowercase();
+        let valid_rest = chars.all(|c| c == '_' || c.is_ascii_lowercase() || c.is_ascii_digit());
+        if !(valid_start && valid_rest) {
+            return Err(IdenError::InvalidCharacters(name.into()));
+        }
+
+        // Only ASCII is left, so the length in bytes is the number of characters
+        if name.len() > MAX_IDEN_LEN {
+            return Err(IdenError::TooLong(name.len()));
+        }
+
+        Ok(Self(name.into()))
+    }
 }
 
 impl Iden for IdenString {
@@ -36,42 +111,496 @@ macro_rules! iden_str {
     };
 }
 
-#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+/// The SQL flavours a statement can be generated for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlBackend {
+    #[default]
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl SqlBackend {
+    /// The characters used by the backend to delimit an identifier.
+    pub fn quote(&self) -> Quote {
+        match self {
+            SqlBackend::Postgres | SqlBackend::Sqlite => Quote::new(b'"'),
+            SqlBackend::MySql => Quote::new(b'`'),
+        }
+    }
+}
+
+/// An identifier that is always quoted as the given [SqlBackend] expects,
+/// independently of the quote the query builder would use by default.
+/// This keeps reserved words (`order`, `select`, ...) safe to use as names.
+pub struct QuotedIden {
+    iden: IdenString,
+    backend: SqlBackend,
+}
+
+impl QuotedIden {
+    pub fn new(iden: IdenString, backend: SqlBackend) -> Self {
+        Self { iden, backend }
+    }
+}
+
+impl Iden for QuotedIden {
+    fn prepare(&self, s: &mut dyn fmt::Write, _: Quote) {
+        let quote = self.backend.quote();
+        write!(s, "{}{}{}", quote.left(), self.quoted(quote), quote.right()).unwrap();
+    }
+
+    fn unquoted(&self, s: &mut dyn fmt::Write) {
+        self.iden.unquoted(s)
+    }
+}
+
+/// The column types a [Field] can hold. A field declares its type through the `type` key,
+/// types with parameters take them as sibling keys, e.g.
+/// `{"name": "price", "type": "decimal", "precision": 10, "scale": 2}`.
+/// On its own, a type is written by its name, `"integer"`, or as its name holding its
+/// parameters, `{"decimal": {"precision": 10, "scale": 2}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Type {
+    #[serde(rename = "smallint")]
+    SmallInt,
+
+    #[serde(rename = "integer")]
+    Integer,
+
+    #[serde(rename = "bigint")]
+    BigInt,
+
+    #[serde(rename = "float")]
+    Float,
+
+    #[serde(rename = "text")]
+    Text,
+
+    #[serde(rename = "bool", alias = "boolean")]
+    Bool,
+
+    #[serde(rename = "decimal")]
+    Decimal { precision: u32, scale: u32 },
+
+    #[serde(rename = "uuid")]
+    Uuid,
+
+    #[serde(rename = "interval")]
+    Interval,
+
+    #[serde(rename = "json")]
+    Json,
+
+    /// A Postgres array of `items`, declared as `{"type": "array", "items": "integer"}`.
+    /// Arrays can not hold other arrays, and on MySQL and SQLite, which have no array
+    /// columns, they are stored as JSON documents.
+    #[serde(rename = "array")]
+    Array {
+        #[serde(
+            deserialize_with = "deserialize_array_items",
+            serialize_with = "serialize_array_items"
+        )]
+        items: Box<Type>,
+    },
+}
+
+/// The form a [Type] takes inside a [Field] declaration, tagged by its `type` key.
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Type", tag = "type")]
+enum TaggedType {
+    #[serde(rename = "smallint")]
+    SmallInt,
+
     #[serde(rename = "integer")]
     Integer,
 
+    #[serde(rename = "bigint")]
+    BigInt,
+
     #[serde(rename = "float")]
     Float,
 
     #[serde(rename = "text")]
     Text,
 
-    #[serde(rename = "bool")]
+    #[serde(rename = "bool", alias = "boolean")]
     Bool,
+
+    #[serde(rename = "decimal")]
+    Decimal { precision: u32, scale: u32 },
+
+    #[serde(rename = "uuid")]
+    Uuid,
+
+    #[serde(rename = "interval")]
+    Interval,
+
+    #[serde(rename = "json")]
+    Json,
+
+    #[serde(rename = "array")]
+    Array {
+        #[serde(
+            deserialize_with = "deserialize_array_items",
+            serialize_with = "serialize_array_items"
+        )]
+        items: Box<Type>,
+    },
 }
 
 #[derive(Debug, Error)]
 pub enum TypeErrors {
     #[error("Could not convert the given type")]
     UnimplementedConversion,
+    #[error("The value {0} can not be stored in a {1:?} column")]
+    IncompatibleValue(serde_json::Value, Type),
+    #[error("The number {0} is too large to be stored exactly, send it as a string instead")]
+    OutOfRange(serde_json::Number),
+    #[error("The value {0} is out of the range of a {1:?} column")]
+    IntegerOverflow(serde_json::Value, Type),
+    #[error("The array mixes {0:?} and {1:?} elements, arrays can only hold one type")]
+    MixedArray(Type, Type),
+    #[error("There are no values to insert")]
+    NoValues,
+}
+
+impl Type {
+    /// The sea_query column type every column of this type is created with on `backend`.
+    pub fn column_type(&self, backend: SqlBackend) -> ColumnType {
+        match self {
+            Type::SmallInt => ColumnType::SmallInteger,
+            Type::Integer => ColumnType::Integer,
+            Type::BigInt => ColumnType::BigInteger,
+            Type::Float => ColumnType::Float,
+            Type::Text => ColumnType::Text,
+            Type::Bool => ColumnType::Boolean,
+            Type::Decimal { precision, scale } => ColumnType::Decimal(Some((*precision, *scale))),
+            Type::Uuid => match backend {
+                // MySQL would make it a binary(16), which the text of a UUID doesn't fit in
+                SqlBackend::MySql => ColumnType::Char(Some(36)),
+                SqlBackend::Postgres | SqlBackend::Sqlite => ColumnType::Uuid,
+            },
+            Type::Interval => match backend {
+                SqlBackend::Postgres => ColumnType::Interval(None, None),
+                // Without an interval column, the ISO-8601 duration is stored as it was sent
+                SqlBackend::MySql | SqlBackend::Sqlite => ColumnType::Text,
+            },
+            Type::Json => ColumnType::JsonBinary,
+            Type::Array { items } => match backend {
+                SqlBackend::Postgres => ColumnType::Array(items.column_type(backend).into()),
+                SqlBackend::MySql | SqlBackend::Sqlite => ColumnType::Json,
+            },
+        }
+    }
+
+    /// The name of the SQL type `backend` stores this type as, e.g. [Type::Float] is a
+    /// `real` on Postgres and a `float` on MySQL. It is rendered by the same query builder
+    /// as the generated statements, so it always matches their columns.
+    pub fn sql_type_name(&self, backend: SqlBackend) -> String {
+        let mut name = String::new();
+        let column_type = self.column_type(backend);
+        match backend {
+            SqlBackend::Postgres => {
+                PostgresQueryBuilder.prepare_column_type(&column_type, &mut name)
+            }
+            SqlBackend::MySql => MysqlQueryBuilder.prepare_column_type(&column_type, &mut name),
+            SqlBackend::Sqlite => SqliteQueryBuilder.prepare_column_type(&column_type, &mut name),
+        }
+        name
+    }
+
+    /// Whether values of this type are numbers, that can be summed, averaged, etc.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Type::SmallInt | Type::Integer | Type::BigInt | Type::Float | Type::Decimal { .. }
+        )
+    }
+
+    /// The JSON Schema (draft-07) describing values of this type.
+    pub fn json_schema(&self) -> serde_json::Value {
+        match self {
+            Type::SmallInt | Type::Integer | Type::BigInt => {
+                serde_json::json!({ "type": "integer" })
+            }
+            Type::Float => serde_json::json!({ "type": "number" }),
+            // Decimals sent as strings keep digits a JSON number would round
+            Type::Decimal { .. } => serde_json::json!({ "type": ["number", "string"] }),
+            Type::Text => serde_json::json!({ "type": "string" }),
+            Type::Bool => serde_json::json!({ "type": "boolean" }),
+            Type::Uuid => serde_json::json!({ "type": "string", "format": "uuid" }),
+            Type::Interval => serde_json::json!({ "type": "string", "format": "duration" }),
+            // Any JSON document is accepted
+            Type::Json => serde_json::json!({}),
+            Type::Array { items } => {
+                serde_json::json!({ "type": "array", "items": items.json_schema() })
+            }
+        }
+    }
+
+    /// A rough estimate of the bytes a value of this type takes in a row.
+    /// Variable length types are counted as their out-of-line (TOAST) pointer.
+    pub fn estimated_bytes(&self) -> usize {
+        match self {
+            Type::SmallInt => 2,
+            Type::Integer | Type::Float => 4,
+            Type::BigInt => 8,
+            Type::Text | Type::Json | Type::Array { .. } => 18,
+            Type::Bool => 1,
+            // Numeric digits are stored in groups of 4, each taking 2 bytes
+            Type::Decimal { precision, .. } => 8 + 2 * (*precision as usize).div_ceil(4),
+            Type::Uuid | Type::Interval => 16,
+        }
+    }
+
+    /// The values a column of this type can hold, for integer types.
+    fn integer_range(&self) -> Option<RangeInclusive<i64>> {
+        match self {
+            Type::SmallInt => Some(i16::MIN.into()..=i16::MAX.into()),
+            Type::Integer => Some(i32::MIN.into()..=i32::MAX.into()),
+            Type::BigInt => Some(i64::MIN..=i64::MAX),
+            _ => None,
+        }
+    }
+
+    /// Checks the parameters of the type are ones the database accepts, describing the
+    /// first that is not.
+    fn check_parameters(&self) -> Result<(), String> {
+        match self {
+            Type::Decimal { precision, .. } if !(1..=MAX_DECIMAL_PRECISION).contains(precision) => {
+                Err(format!(
+                    "decimal precision ({precision}) must be between 1 and {MAX_DECIMAL_PRECISION}"
+                ))
+            }
+            Type::Decimal { precision, scale } if scale > precision => Err(format!(
+                "decimal scale ({scale}) can not be greater than its precision ({precision})"
+            )),
+            Type::Array { items } if matches!(items.as_ref(), Type::Array { .. }) => {
+                Err("arrays can not hold other arrays".into())
+            }
+            Type::Array { items } => items.check_parameters(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Converts a JSON value into a SQL value of this type, stored in a `backend` column.
+    fn sql_value(
+        &self,
+        value: &serde_json::Value,
+        backend: SqlBackend,
+    ) -> Result<Value, TypeErrors> {
+        let incompatible = || TypeErrors::IncompatibleValue(value.clone(), self.clone());
+        match self {
+            Type::SmallInt | Type::Integer | Type::BigInt => {
+                let integer = value.as_i64().ok_or_else(incompatible)?;
+                match self.integer_range() {
+                    Some(range) if range.contains(&integer) => Ok(Value::from(integer)),
+                    _ => Err(TypeErrors::IntegerOverflow(value.clone(), self.clone())),
+                }
+            }
+            Type::Float => value.as_f64().map(Value::from).ok_or_else(incompatible),
+            // Sent as text, which the database converts to the column's numeric type without
+            // the rounding a float would go through
+            Type::Decimal { .. } => decimal_literal(value)
+                .map(Value::from)
+                .ok_or_else(incompatible),
+            Type::Text | Type::Uuid | Type::Interval => {
+                value.as_str().map(Value::from).ok_or_else(incompatible)
+            }
+            Type::Bool => value.as_bool().map(Value::from).ok_or_else(incompatible),
+            // Nested documents are stored as their JSON text
+            Type::Json => Ok(Value::from(value.to_string())),
+            Type::Array { .. } if backend != SqlBackend::Postgres => value
+                .is_array()
+                .then(|| Value::from(value.to_string()))
+                .ok_or_else(incompatible),
+            // A text array would need an explicit cast to become a numeric, uuid, interval or
+            // jsonb one, while an array literal is parsed by the column type itself
+            Type::Array { items }
+                if matches!(
+                    items.as_ref(),
+                    Type::Decimal { .. } | Type::Uuid | Type::Interval | Type::Json
+                ) =>
+            {
+                let literals = value
+                    .as_array()
+                    .ok_or_else(incompatible)?
+                    .iter()
+                    .map(|value| {
+                        let literal = match items.as_ref() {
+                            Type::Decimal { .. } => decimal_literal(value),
+                            Type::Json => Some(value.to_string()),
+                            _ => value.as_str().map(String::from),
+                        };
+                        let literal = literal.ok_or_else(|| {
+                            TypeErrors::IncompatibleValue(value.clone(), *items.clone())
+                        })?;
+                        // Quoted, so the elements can't be split or nested by the literal
+                        Ok(format!(
+                            "\"{}\"",
+                            literal.replace('\\', "\\\\").replace('"', "\\\"")
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Value::from(format!("{{{}}}", literals.join(","))))
+            }
+            Type::Array { items } => {
+                let array_type = match items.as_ref() {
+                    Type::SmallInt | Type::Integer | Type::BigInt => ArrayType::BigInt,
+                    Type::Float => ArrayType::Double,
+                    Type::Bool => ArrayType::Bool,
+                    _ => ArrayType::String,
+                };
+                let values = value
+                    .as_array()
+                    .ok_or_else(incompatible)?
+                    .iter()
+                    .map(|value| items.sql_value(value, backend))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Value::Array(array_type, Some(Box::new(values))))
+            }
+        }
+    }
+
+    /// Infers the type of a JSON array. Arrays of scalars become an array of their type,
+    /// integers and floats widening to the widest of them and strings to text, while empty
+    /// arrays and arrays holding nulls, objects or arrays are kept as JSON documents.
+    fn infer_array(values: &[serde_json::Value]) -> Result<Type, TypeErrors> {
+        let is_document = |v: &serde_json::Value| v.is_null() || v.is_array() || v.is_object();
+        if values.is_empty() || values.iter().any(is_document) {
+            return Ok(Type::Json);
+        }
+
+        let mut items = Type::try_from(&values[0])?;
+        for value in &values[1..] {
+            items = match (items, Type::try_from(value)?) {
+                (a, b) if a == b => a,
+                (Type::Integer | Type::BigInt, Type::Integer | Type::BigInt) => Type::BigInt,
+                (a, b) if a.is_numeric() && b.is_numeric() => Type::Float,
+                (
+                    Type::Text | Type::Uuid | Type::Interval,
+                    Type::Text | Type::Uuid | Type::Interval,
+                ) => Type::Text,
+                (a, b) => return Err(TypeErrors::MixedArray(a, b)),
+            };
+        }
+
+        Ok(Type::Array {
+            items: Box::new(items),
+        })
+    }
+}
+
+/// The exact decimal literal of a JSON number, or of a string holding one, e.g. `"12.50"`
+/// or `-1.5e3`. Strings keep every digit, so values a float can't represent are not rounded.
+fn decimal_literal(value: &serde_json::Value) -> Option<String> {
+    let literal = match value {
+        serde_json::Value::Number(number) => return Some(number.to_string()),
+        serde_json::Value::String(literal) => literal,
+        _ => return None,
+    };
+
+    fn unsigned(s: &str) -> &str {
+        s.strip_prefix(['-', '+']).unwrap_or(s)
+    }
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+    let (mantissa, exponent) = literal.split_once(['e', 'E']).unwrap_or((literal, "0"));
+    let (int, frac) = unsigned(mantissa)
+        .split_once('.')
+        .unwrap_or((unsigned(mantissa), ""));
+    let exponent = unsigned(exponent);
+
+    let valid_mantissa = !(int.is_empty() && frac.is_empty()) && digits(int) && digits(frac);
+    let valid_exponent = !exponent.is_empty() && digits(exponent);
+    (valid_mantissa && valid_exponent).then(|| literal.clone())
+}
+
+/// Whether the string is a hyphenated UUID, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Whether the string is an ISO-8601 duration, e.g. `PT1H`, `P1Y2M10DT2H30M` or `P2W`.
+/// Only the last component may carry a fraction.
+fn is_iso8601_duration(s: &str) -> bool {
+    let Some(rest) = s.strip_prefix('P') else {
+        return false;
+    };
+    if rest.ends_with('T') {
+        return false;
+    }
+    let (date, time) = rest.split_once('T').unwrap_or((rest, ""));
+
+    // Splits a part into the numbers of its <number><designator> components,
+    // the designators have to show up in the given order
+    fn components<'a>(part: &'a str, designators: &[char]) -> Option<Vec<&'a str>> {
+        let mut remaining = designators;
+        let mut numbers = Vec::new();
+        let mut start = 0;
+        for (i, c) in part.char_indices() {
+            if c.is_ascii_digit() || c == '.' || c == ',' {
+                continue;
+            }
+            let position = remaining.iter().position(|d| *d == c)?;
+            remaining = &remaining[position + 1..];
+            numbers.push(&part[start..i]);
+            start = i + 1;
+        }
+        (start == part.len()).then_some(numbers)
+    }
+
+    let (Some(mut numbers), Some(time)) = (
+        components(date, &['Y', 'M', 'W', 'D']),
+        components(time, &['H', 'M', 'S']),
+    ) else {
+        return false;
+    };
+    numbers.extend(time);
+
+    let is_integer = |n: &str| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit());
+    match numbers.split_last() {
+        Some((last, leading)) => {
+            let (whole, fraction) = last.split_once(['.', ',']).unwrap_or((last, "0"));
+            leading.iter().all(|n| is_integer(n)) && is_integer(whole) && is_integer(fraction)
+        }
+        None => false,
+    }
 }
 
+/// 2^53, the first integer a f64 can not tell apart from its successor.
+const MAX_EXACT_FLOAT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
 impl<'a> TryFrom<&'a serde_json::Value> for Type {
     type Error = TypeErrors;
 
     fn try_from(value: &'a serde_json::Value) -> Result<Self, Self::Error> {
         match value {
-            serde_json::Value::Null
-            | serde_json::Value::Array(_)
-            | serde_json::Value::Object(_) => Err(TypeErrors::UnimplementedConversion),
-            serde_json::Value::Number(n) => Ok({
-                if n.is_i64() {
-                    Type::Integer
-                } else {
-                    Type::Float
-                }
-            }),
+            serde_json::Value::Null => Err(TypeErrors::UnimplementedConversion),
+            serde_json::Value::Array(values) => Type::infer_array(values),
+            serde_json::Value::Object(_) => Ok(Type::Json),
+            // Integers that do not fit in 32 bits need a 64 bit column
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) if i.unsigned_abs() > i32::MAX as u64 => Ok(Type::BigInt),
+                Some(_) => Ok(Type::Integer),
+                // Integers beyond i64 are either a u64 or were rounded into a f64, which
+                // only holds integers up to 2^53 exactly, both would be stored corrupted.
+                // A f64 can not tell `1e20` from a rounded integer, so both are rejected
+                None if n.is_u64() => Err(TypeErrors::OutOfRange(n.clone())),
+                None => match n.as_f64() {
+                    Some(f) if f.fract() == 0.0 && f.abs() >= MAX_EXACT_FLOAT_INTEGER => {
+                        Err(TypeErrors::OutOfRange(n.clone()))
+                    }
+                    _ => Ok(Type::Float),
+                },
+            },
+            serde_json::Value::String(s) if is_uuid(s) => Ok(Type::Uuid),
+            serde_json::Value::String(s) if is_iso8601_duration(s) => Ok(Type::Interval),
             serde_json::Value::String(_) => Ok(Type::Text),
             serde_json::Value::Bool(_) => Ok(Type::Bool),
         }
@@ -82,10 +611,146 @@ impl<'a> TryFrom<&'a serde_json::Value> for Type {
 #[getset(get = "pub")]
 pub struct Field {
     name: String,
-    #[serde(rename = "type")]
+    #[serde(
+        flatten,
+        deserialize_with = "deserialize_field_type",
+        serialize_with = "TaggedType::serialize"
+    )]
     field_type: Type,
     #[serde(default)]
     nullable: bool,
+    /// Pins the position of the column in the generated table, see [Schema::ordered_fields].
+    #[serde(default)]
+    ordinal: Option<u32>,
+    /// Bounds a text column, making it a `varchar(length)`.
+    #[serde(default)]
+    length: Option<u32>,
+    /// Makes an integer column draw its values from a sequence, without it being the
+    /// primary key. MySQL and SQLite only allow this on the primary key column.
+    #[serde(default)]
+    auto_increment: bool,
+    /// Makes the column a foreign key to a column of another table.
+    #[serde(default)]
+    references: Option<Reference>,
+    /// Rejects blank strings in a text column with a `CHECK (length(trim(col)) > 0)`.
+    /// Being its own check constraint, it composes with any other check on the column.
+    #[serde(default)]
+    non_empty: bool,
+    /// A raw SQL boolean expression the column's values must satisfy, e.g.
+    /// `temperature between -50 and 150`. It is written into the statement as it is, and
+    /// only checked to be non-empty, with parentheses balanced outside of quotes, closed
+    /// quotes and without semicolons, comments, backslashes or dollar signs, so it can't
+    /// close the check clause or hide what follows it. That does not make it a valid
+    /// expression, which is left for the database to judge.
+    #[serde(default)]
+    check: Option<String>,
+    /// Adds a unique constraint to the column.
+    #[serde(default)]
+    unique: bool,
+}
+
+/// The column of another table a [Field] points to, e.g.
+/// `{"name": "device_id", "type": "integer", "references": {"table": "devices", "column": "id"}}`.
+#[derive(Debug, Clone, Deserialize, Serialize, Getters, PartialEq, Eq)]
+#[getset(get = "pub")]
+pub struct Reference {
+    table: String,
+    column: String,
+}
+
+impl Field {
+    /// Builds the column definition for this field, with the name quoted for `backend`.
+    pub fn column_def(&self, backend: SqlBackend) -> ColumnDef {
+        let mut column = ColumnDef::new_with_type(
+            QuotedIden::new(iden_str!(self.name()), backend),
+            self.column_type(backend),
+        );
+
+        self.nullable().then(|| column.null());
+        self.auto_increment().then(|| column.auto_increment());
+        self.unique().then(|| column.unique_key());
+        self.non_empty().then(|| {
+            // Written out by hand, only the Postgres builder would fill a `$1` placeholder
+            let mut quoted = String::new();
+            QuotedIden::new(iden_str!(self.name()), backend).prepare(&mut quoted, backend.quote());
+            column.check(Expr::cust(format!("length(trim({quoted})) > 0")))
+        });
+        if let Some(check) = self.check() {
+            column.check(Expr::cust(check));
+        }
+        column
+    }
+
+    /// The column type of this field on `backend`, its [Type] refined by the field's options.
+    pub fn column_type(&self, backend: SqlBackend) -> ColumnType {
+        match (self.field_type(), self.length()) {
+            (Type::Text, Some(length)) => ColumnType::String(Some(*length)),
+            (field_type, _) => field_type.column_type(backend),
+        }
+    }
+
+    /// Builds the statement adding this field as a new column of an existing table,
+    /// with every identifier quoted for `backend`.
+    pub fn add_column_statement(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> TableAlterStatement {
+        Table::alter()
+            .table(QuotedIden::new(iden_str!(table_name), backend))
+            .add_column(&mut self.column_def(backend))
+            .to_owned()
+    }
+
+    /// Builds the statement changing the existing column of this field to its current
+    /// type and nullability, with every identifier quoted for `backend`.
+    /// Nullability is written as on table creation, only nullable columns are marked.
+    /// Constraints (unique, checks...) are left as they are, as adding them again
+    /// would duplicate those the column already has.
+    /// SQLite can't modify columns, rendering the statement for it panics.
+    pub fn modify_column_statement(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> TableAlterStatement {
+        self.modify_column(table_name, backend, true)
+    }
+
+    /// Same as [Field::modify_column_statement], but the nullability of the column is only
+    /// written when `nullability` is set.
+    fn modify_column(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+        nullability: bool,
+    ) -> TableAlterStatement {
+        let mut column = ColumnDef::new_with_type(
+            QuotedIden::new(iden_str!(self.name()), backend),
+            self.column_type(backend),
+        );
+        (nullability && *self.nullable()).then(|| column.null());
+
+        Table::alter()
+            .table(QuotedIden::new(iden_str!(table_name), backend))
+            .modify_column(&mut column)
+            .to_owned()
+    }
+
+    /// Converts a JSON value into the SQL value stored in this field's column.
+    /// Values are converted for [SqlBackend::Postgres].
+    pub fn sql_value(&self, value: &serde_json::Value) -> Result<Value, TypeErrors> {
+        self.sql_value_for(value, SqlBackend::default())
+    }
+
+    /// Same as [Field::sql_value], but the value is converted for a `backend` column,
+    /// which only makes a difference for arrays.
+    pub fn sql_value_for(
+        &self,
+        value: &serde_json::Value,
+        backend: SqlBackend,
+    ) -> Result<Value, TypeErrors> {
+        self.field_type().sql_value(value, backend)
+    }
 }
 
 impl PartialEq for Field {
@@ -114,102 +779,1237 @@ impl LiveSchema {
     /// is backend agnostic, the translation to a specific flavor of SQL is done with a
     /// QueryBuilder, the query builder _used for testing_ is the
     /// [PostgresQueryBuilder](sea_query::PostgresQueryBuilder).
-    pub fn table_create_statement<'a>(&self, table_name: &'a str) -> TableCreateStatement {
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    ///
+    /// The columns are always created sorted by name, since the order the fields were
+    /// read in depends on the JSON map implementation, see [LiveSchema::ordered_fields].
+    pub fn table_create_statement(&self, table_name: &str) -> TableCreateStatement {
+        self.table_create_statement_for(table_name, SqlBackend::default())
+    }
+
+    /// The inferred fields sorted by name, the order their columns are created in.
+    pub fn ordered_fields(&self) -> Vec<&Field> {
+        let mut fields = self
+            .inner()
+            .iter()
+            .flatten()
+            .map(|(f, _)| f)
+            .collect::<Vec<_>>();
+        fields.sort_by(|l, r| l.name().cmp(r.name()));
+        fields
+    }
+
+    /// Same as [LiveSchema::table_create_statement], but every identifier is quoted
+    /// the way `backend` expects, whatever query builder ends up rendering it.
+    pub fn table_create_statement_for(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> TableCreateStatement {
+        self.create_statement(table_name, backend, &[])
+    }
+
+    /// Same as [LiveSchema::table_create_statement], but the columns of the fields named
+    /// in `nullable_fields` accept nulls. Inferred fields are never nullable on their own,
+    /// as a single live value can't tell if later ones may be missing.
+    /// Names that don't match any field are ignored.
+    pub fn table_create_statement_with_nullable(
+        &self,
+        table_name: &str,
+        nullable_fields: &[&str],
+    ) -> TableCreateStatement {
+        self.create_statement(table_name, SqlBackend::default(), nullable_fields)
+    }
+
+    fn create_statement(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+        nullable_fields: &[&str],
+    ) -> TableCreateStatement {
         // The table create statement is done using a constructor that is builder like.
         let mut statement = Table::create();
         // The iden_str! macro here, allows us to provide a runtime String, as the table name
-        statement.table(iden_str!(table_name));
+        statement.table(QuotedIden::new(iden_str!(table_name), backend));
 
         // Go through each Field in the vec and create a corresponding column for it
-        for entry in self.inner().iter() {
-            if entry.is_none() {
-                continue;
+        for entry in self.ordered_fields() {
+            let mut column = entry.column_def(backend);
+            nullable_fields
+                .contains(&entry.name().as_str())
+                .then(|| column.null());
+            statement.col(&mut column);
+        }
+
+        statement
+            .col(&mut IdStrategy::Serial.id_column_def(DEFAULT_ID_COLUMN, backend))
+            .to_owned()
+    }
+
+    /// Generates an insert statement storing the captured values in the inferred columns.
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    pub fn insert_statement(&self, table_name: &str) -> Result<InsertStatement, TypeErrors> {
+        self.insert_statement_for(table_name, SqlBackend::default())
+    }
+
+    /// Same as [LiveSchema::insert_statement], but every identifier is quoted
+    /// the way `backend` expects.
+    pub fn insert_statement_for(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> Result<InsertStatement, TypeErrors> {
+        // An insert without columns renders as invalid SQL on every backend
+        if self.inner().iter().flatten().next().is_none() {
+            return Err(TypeErrors::NoValues);
+        }
+
+        let mut columns = Vec::with_capacity(self.inner().len());
+        let mut values = Vec::with_capacity(self.inner().len());
+
+        for (field, value) in self.inner().iter().flatten() {
+            columns.push(QuotedIden::new(iden_str!(field.name()), backend));
+            values.push(field.sql_value_for(value, backend)?.into());
+        }
+
+        Ok(Query::insert()
+            .into_table(QuotedIden::new(iden_str!(table_name), backend))
+            .columns(columns)
+            .values_panic(values)
+            .to_owned())
+    }
+
+    /// Generates a single insert statement storing a batch of records, the columns are the
+    /// fields inferred from the first record. Every other record must hold the same fields,
+    /// with values that can be stored in the first record's columns.
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    pub fn batch_insert_statement(
+        records: &[LiveSchema],
+        table_name: &str,
+    ) -> Result<InsertStatement, BatchError> {
+        Self::batch_insert_statement_for(records, table_name, SqlBackend::default())
+    }
+
+    /// Same as [LiveSchema::batch_insert_statement], but every identifier is quoted
+    /// the way `backend` expects.
+    pub fn batch_insert_statement_for(
+        records: &[LiveSchema],
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> Result<InsertStatement, BatchError> {
+        let first = records.first().ok_or(BatchError::Empty)?;
+        let fields = first
+            .inner()
+            .iter()
+            .flatten()
+            .map(|(f, _)| f)
+            .collect::<Vec<_>>();
+        // An insert without columns renders as invalid SQL on every backend
+        if fields.is_empty() {
+            return Err(BatchError::NoFields);
+        }
+
+        let mut statement = Query::insert();
+        statement
+            .into_table(QuotedIden::new(iden_str!(table_name), backend))
+            .columns(
+                fields
+                    .iter()
+                    .map(|field| QuotedIden::new(iden_str!(field.name()), backend)),
+            );
+
+        for (record, live_schema) in records.iter().enumerate() {
+            if live_schema.inner().iter().flatten().count() != fields.len() {
+                return Err(BatchError::DivergentFields { record });
             }
-            let (entry, _) = entry.as_ref().unwrap();
-            let mut column = ColumnDef::new(iden_str!(entry.name()));
 
-            entry.nullable().then(|| column.null());
+            let mut values = Vec::with_capacity(fields.len());
+            for field in fields.iter() {
+                let (_, value) = live_schema
+                    .inner()
+                    .iter()
+                    .flatten()
+                    .find(|(f, _)| f.name() == field.name())
+                    .ok_or(BatchError::DivergentFields { record })?;
+                let value = field
+                    .sql_value_for(value, backend)
+                    .map_err(|source| BatchError::IncompatibleValue { record, source })?;
+                values.push(value.into());
+            }
+            statement.values_panic(values);
+        }
 
-            let col_type = match entry.field_type() {
-                Type::Integer => column.integer(),
-                Type::Float => column.float(),
-                Type::Text => column.text(),
-                Type::Bool => column.boolean(),
-            };
-            statement.col(col_type);
+        Ok(statement)
+    }
+
+    /// Same as [LiveSchema::batch_insert_statement], but records colliding with an existing
+    /// row of `schema`'s table are handled by `strategy`, see [ConflictStrategy].
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    pub fn batch_upsert_statement(
+        records: &[LiveSchema],
+        table_name: &str,
+        schema: &Schema,
+        strategy: ConflictStrategy,
+    ) -> Result<InsertStatement, BatchError> {
+        Self::batch_upsert_statement_for(
+            records,
+            table_name,
+            schema,
+            strategy,
+            SqlBackend::default(),
+        )
+    }
+
+    /// Same as [LiveSchema::batch_upsert_statement], but every identifier is quoted
+    /// the way `backend` expects.
+    ///
+    /// Every strategy but [ConflictStrategy::Error] needs the conflict target of `schema`,
+    /// see [Schema::conflict_target], and the batch must provide all of its columns.
+    pub fn batch_upsert_statement_for(
+        records: &[LiveSchema],
+        table_name: &str,
+        schema: &Schema,
+        strategy: ConflictStrategy,
+        backend: SqlBackend,
+    ) -> Result<InsertStatement, BatchError> {
+        let mut statement = Self::batch_insert_statement_for(records, table_name, backend)?;
+        if strategy == ConflictStrategy::Error {
+            return Ok(statement);
+        }
+
+        let target = schema
+            .conflict_target()
+            .ok_or(BatchError::NoConflictTarget)?;
+        let provided = records[0]
+            .inner()
+            .iter()
+            .flatten()
+            .map(|(f, _)| f.name())
+            .collect::<Vec<_>>();
+
+        if let Some(column) = target.iter().find(|column| !provided.contains(column)) {
+            return Err(BatchError::MissingConflictColumn {
+                column: column.clone(),
+            });
+        }
+
+        let quoted = |name: &str| QuotedIden::new(iden_str!(name), backend);
+        let updated = provided
+            .iter()
+            .filter(|name| !target.contains(name))
+            .map(|name| quoted(name))
+            .collect::<Vec<_>>();
+
+        // The columns Replace sets to null, those the batch does not provide
+        let nulled = schema
+            .inner()
+            .iter()
+            .flatten()
+            .map(Field::name)
+            .filter(|name| !target.contains(name) && !provided.contains(name))
+            .map(|name| (quoted(name), SimpleExpr::Keyword(Keyword::Null)))
+            .collect::<Vec<_>>();
+
+        let skip = match strategy {
+            ConflictStrategy::Error => unreachable!(),
+            ConflictStrategy::Ignore => true,
+            // With every provided column in the target, there is nothing left to update
+            ConflictStrategy::Merge => updated.is_empty(),
+            ConflictStrategy::Replace => updated.is_empty() && nulled.is_empty(),
+        };
+
+        let mut on_conflict = OnConflict::columns(target.iter().map(|column| quoted(column)));
+        match (skip, strategy) {
+            // MySQL has no DO NOTHING, assigning a column to itself leaves the row as it is
+            (true, _) if backend == SqlBackend::MySql => {
+                let column = &target[0];
+                on_conflict.values([(quoted(column), Expr::col(quoted(column)).into())])
+            }
+            (true, _) => on_conflict.do_nothing(),
+            (false, ConflictStrategy::Replace) => {
+                on_conflict.update_columns(updated).values(nulled)
+            }
+            (false, _) => on_conflict.update_columns(updated),
+        };
+
+        statement.on_conflict(on_conflict);
+        Ok(statement)
+    }
+}
+
+/// What a batch insert does with the records colliding with an existing row on the
+/// table's conflict target, see [LiveSchema::batch_upsert_statement].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// The whole insert fails
+    #[default]
+    Error,
+    /// The colliding records are skipped
+    Ignore,
+    /// The existing row is overwritten, the columns the batch does not provide are set to null
+    Replace,
+    /// Only the columns the batch provides are updated
+    Merge,
+}
+
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("The batch has no records")]
+    Empty,
+    #[error("The first record of the batch has no fields")]
+    NoFields,
+    #[error("Record {record} does not have the same fields as the first record")]
+    DivergentFields { record: usize },
+    #[error("Record {record} has an incompatible value: {source}")]
+    IncompatibleValue { record: usize, source: TypeErrors },
+    #[error("The schema has no primary key, unique index or unique field to detect conflicting records with")]
+    NoConflictTarget,
+    #[error("The records do not provide the conflict target column {column}")]
+    MissingConflictColumn { column: String },
+}
+
+/// How the synthetic `id` primary key of a [Schema]'s table is generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdStrategy {
+    /// An auto-increment integer
+    #[default]
+    Serial,
+    /// A random UUID, generated by the database: `gen_random_uuid()` on Postgres,
+    /// `uuid()` on MySQL and random bytes on SQLite
+    Uuid,
+}
+
+impl IdStrategy {
+    /// The column type the synthetic `id` gets with this strategy.
+    pub fn id_type(&self) -> Type {
+        match self {
+            IdStrategy::Serial => Type::Integer,
+            IdStrategy::Uuid => Type::Uuid,
+        }
+    }
+
+    /// Builds the synthetic primary key column named `name`, quoted for `backend`.
+    pub fn id_column_def(&self, name: &str, backend: SqlBackend) -> ColumnDef {
+        let mut id = ColumnDef::new_with_type(
+            QuotedIden::new(iden_str!(name), backend),
+            self.id_type().column_type(backend),
+        );
+        match self {
+            IdStrategy::Serial => id.not_null().auto_increment(),
+            IdStrategy::Uuid => id.not_null().default(Expr::cust(match backend {
+                SqlBackend::Postgres => "gen_random_uuid()",
+                // Expression defaults need parentheses on MySQL
+                SqlBackend::MySql => "(uuid())",
+                // SQLite has no UUID function, a version 4 UUID is put together from random bytes
+                SqlBackend::Sqlite => {
+                    "(lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' || \
+                    substr(lower(hex(randomblob(2))), 2) || '-' || \
+                    substr('89ab', 1 + (abs(random()) % 4), 1) || \
+                    substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6))))"
+                }
+            })),
+        };
+        id.primary_key().to_owned()
+    }
+}
+
+/// What happens when a [Schema] declares a field named `id` (or its `id_column`), which
+/// collides with the synthetic primary key. Schemas with an explicit `primary_key` have no
+/// synthetic column, so there the field is kept as any other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdFieldPolicy {
+    /// The schema is rejected
+    #[default]
+    Reject,
+    /// The declared field becomes the primary key, replacing the synthetic one
+    PrimaryKey,
+}
+
+/// A secondary index created alongside a [Schema]'s table.
+/// Its name is validated with [IdenString::try_new], as the query builder
+/// writes index names as they are.
+#[derive(Debug, Clone, Deserialize, Serialize, Getters, PartialEq, Eq)]
+#[getset(get = "pub")]
+pub struct IndexSpec {
+    name: String,
+    columns: Vec<String>,
+    #[serde(default)]
+    unique: bool,
+}
+
+impl IndexSpec {
+    /// Builds the create index statement over `table_name`, the table and columns
+    /// are quoted for `backend`.
+    pub fn index_create_statement(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> IndexCreateStatement {
+        let mut statement = Index::create();
+        statement
+            .name(self.name())
+            .table(QuotedIden::new(iden_str!(table_name), backend));
+
+        for column in self.columns() {
+            statement.col(QuotedIden::new(iden_str!(column), backend));
         }
 
-        let mut table_unique_id = ColumnDef::new(iden_str!("id"));
-        table_unique_id.integer().not_null().auto_increment();
+        self.unique().then(|| statement.unique());
+        statement
+    }
+}
+
+/// Thresholds a [Schema] is checked against, before its table gets created.
+/// The defaults follow the Postgres limits: at most 1600 columns and a row
+/// that fits in a single 8KB page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaLimits {
+    pub max_columns: usize,
+    pub max_row_bytes: usize,
+}
 
-        statement.col(table_unique_id.primary_key()).to_owned()
+impl Default for SchemaLimits {
+    fn default() -> Self {
+        Self {
+            max_columns: 1600,
+            max_row_bytes: 8160,
+        }
     }
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SchemaLimitError {
+    #[error("The schema declares {count} columns, the maximum allowed is {max}")]
+    TooManyColumns { count: usize, max: usize },
+    #[error("The schema rows are estimated at {estimate} bytes, the maximum allowed is {max}")]
+    RowTooWide { estimate: usize, max: usize },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SchemaBackendError {
+    #[error("The auto_increment field {field:?} must lead the primary key on {backend:?}")]
+    AutoIncrementOutsidePrimaryKey { field: String, backend: SqlBackend },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SchemaDiffError {
+    #[error("SQLite can not modify the column {0:?}, its table has to be created again")]
+    UnsupportedModify(String),
+}
+
+/// Why [Schema::validate] rejected a schema. The deserializer goes through the same
+/// checks, so these are also the messages of its errors.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SchemaValidationError {
+    #[error("The schema declares no fields")]
+    Empty,
+    #[error("the schema declares {0} fields, at most {MAX_FIELDS} are allowed")]
+    TooManyFields(usize),
+    #[error("field {0:?} has the same column name as another field")]
+    DuplicateField(String),
+    #[error("the identifier {name:?} is invalid: {source}")]
+    InvalidIdentifier { name: String, source: IdenError },
+    #[error("field {name:?} has an invalid type: {reason}")]
+    InvalidType { name: String, reason: String },
+    #[error("length of field {0:?} must be greater than 0")]
+    ZeroLength(String),
+    #[error("field {0:?} with a length must be text")]
+    LengthOnNonText(String),
+    #[error("non_empty field {0:?} must be text")]
+    NonEmptyOnNonText(String),
+    #[error(
+        "check of field {0:?} must be a non-empty expression with balanced parentheses \
+        and quotes, without semicolons, comments, backslashes or dollar signs"
+    )]
+    UnsafeCheck(String),
+    #[error("auto_increment field {0:?} can not be nullable")]
+    NullableAutoIncrement(String),
+    #[error("auto_increment field {0:?} must be an integer")]
+    NonIntegerAutoIncrement(String),
+    #[error("only one field per schema can be auto_increment")]
+    MultipleAutoIncrement,
+    #[error(
+        "field {0:?} collides with the synthetic id primary key, \
+        rename it or set \"id_field\": \"primary_key\" to use it as the primary key"
+    )]
+    IdFieldCollision(String),
+    #[error(
+        "field {0:?} collides with the timestamp columns, \
+        rename it or set \"timestamps\": false"
+    )]
+    TimestampCollision(String),
+    #[error("primary key column {0:?} is not a declared field")]
+    UnknownPrimaryKeyColumn(String),
+    #[error("primary key column {0:?} is listed more than once")]
+    RepeatedPrimaryKeyColumn(String),
+    #[error("index {0:?} is declared more than once")]
+    DuplicateIndex(String),
+    #[error("index {0:?} has no columns")]
+    EmptyIndex(String),
+    #[error("index {index:?} column {column:?} is not a declared field")]
+    UnknownIndexColumn { index: String, column: String },
+}
+
+/// The most fields a [Schema] can declare when deserialized, keeping well below
+/// the 1600 columns Postgres allows in a table.
+pub const MAX_FIELDS: usize = 1000;
+
+/// The most digits a [Type::Decimal] can hold, the limit Postgres puts on a declared precision.
+pub const MAX_DECIMAL_PRECISION: u32 = 1000;
+
+/// The deepest a JSON value of a [LiveSchema] may nest objects and arrays by default,
+/// see [LiveSchemaSeed::max_json_depth].
+pub const MAX_JSON_DEPTH: usize = 32;
+
+/// How deep a JSON value nests objects and arrays, scalars have a depth of 0.
+/// Walks the value without recursion, so any depth can be measured.
+fn json_depth(value: &serde_json::Value) -> usize {
+    let mut deepest = 0;
+    let mut pending = vec![(value, 0)];
+    while let Some((value, depth)) = pending.pop() {
+        match value {
+            serde_json::Value::Array(values) => {
+                pending.extend(values.iter().map(|v| (v, depth + 1)));
+                deepest = deepest.max(depth + 1);
+            }
+            serde_json::Value::Object(values) => {
+                pending.extend(values.values().map(|v| (v, depth + 1)));
+                deepest = deepest.max(depth + 1);
+            }
+            _ => {}
+        }
+    }
+    deepest
+}
+
 /// A **Schema** is an abstraction placed bettwen the JSON schema,
 /// and the adequeate SQL syntax to represent said schema, as a table.
 /// Right now, a schema supports only data types present in the _enum_ [Type]
+///
+/// A schema is declared either as the bare list of its fields, or as an object
+/// holding the `fields` list together with table level options:
+/// - `primary_key`: the names of the fields making up a (composite) primary key,
+///   when given, the synthetic `id` column is not created.
+/// - `indexes`: secondary indexes to create over the table, see [IndexSpec].
+/// - `id_strategy`: how the synthetic `id` is generated, see [IdStrategy].
+/// - `id_field`: what to do with a declared field named `id`, see [IdFieldPolicy],
+///   rejecting it by default.
+/// - `id_column`: the name of the synthetic primary key column, `id` by default.
+/// - `timestamps`: when true, the table gets `created_at` and `updated_at` columns
+///   defaulting to the current timestamp, which no declared field may be named.
 #[derive(Debug, Default, Serialize)]
-pub struct Schema(Vec<Option<Field>>);
+pub struct Schema {
+    fields: Vec<Option<Field>>,
+    primary_key: Vec<String>,
+    indexes: Vec<IndexSpec>,
+    id_strategy: IdStrategy,
+    id_column: Option<String>,
+    timestamps: bool,
+}
+
+/// The name of the synthetic primary key column, unless a schema declares its `id_column`.
+pub const DEFAULT_ID_COLUMN: &str = "id";
+
+/// The columns added to the tables of schemas declaring `timestamps`.
+const TIMESTAMP_COLUMNS: [&str; 2] = ["created_at", "updated_at"];
 
 impl Schema {
     pub fn inner(&self) -> &[Option<Field>] {
-        &self.0
+        &self.fields
     }
-    fn inner_mut(&mut self) -> &mut [Option<Field>] {
-        &mut self.0
+
+    pub fn primary_key(&self) -> &[String] {
+        &self.primary_key
+    }
+
+    pub fn indexes(&self) -> &[IndexSpec] {
+        &self.indexes
+    }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.id_strategy
+    }
+
+    /// The name of the synthetic primary key column.
+    pub fn id_column(&self) -> &str {
+        self.id_column.as_deref().unwrap_or(DEFAULT_ID_COLUMN)
+    }
+
+    pub fn timestamps(&self) -> bool {
+        self.timestamps
+    }
+
+    /// Whether the schema declares no fields, its table would only hold the synthetic `id`,
+    /// which is almost never what was meant.
+    pub fn is_empty(&self) -> bool {
+        self.inner().iter().flatten().next().is_none()
+    }
+
+    /// The columns conflicting records are detected on: the declared primary key, or else
+    /// the columns of the first unique index, or else the first field declared `unique`.
+    /// The synthetic `id` is never a conflict target, records do not carry it.
+    pub fn conflict_target(&self) -> Option<&[String]> {
+        if !self.primary_key.is_empty() {
+            return Some(self.primary_key());
+        }
+
+        let unique_index = self
+            .indexes()
+            .iter()
+            .find(|index| *index.unique())
+            .map(|index| index.columns().as_slice());
+        let unique_field = || {
+            self.inner()
+                .iter()
+                .flatten()
+                .find(|field| *field.unique())
+                .map(|field| std::slice::from_ref(field.name()))
+        };
+
+        unique_index.or_else(unique_field)
+    }
+
+    /// Whether the table gets the auto-increment `id` column as its primary key.
+    fn has_synthetic_id(&self) -> bool {
+        self.primary_key.is_empty()
+    }
+
+    /// Generates every statement needed to create the schema's table: the create table
+    /// statement, followed by the create statements of its indexes, which should be
+    /// executed after the table exists.
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    pub fn create_statements(
+        &self,
+        table_name: &str,
+    ) -> (TableCreateStatement, Vec<IndexCreateStatement>) {
+        self.create_statements_for(table_name, SqlBackend::default())
+    }
+
+    /// Same as [Schema::create_statements], but every identifier is quoted
+    /// the way `backend` expects.
+    pub fn create_statements_for(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> (TableCreateStatement, Vec<IndexCreateStatement>) {
+        let indexes = self
+            .indexes()
+            .iter()
+            .map(|index| index.index_create_statement(table_name, backend))
+            .collect();
+
+        (
+            self.table_create_statement_for(table_name, backend),
+            indexes,
+        )
+    }
+
+    /// Renders the create table statement as SQL for the given backend, using
+    /// the matching query builder.
+    pub fn to_sql(&self, table_name: &str, backend: SqlBackend) -> String {
+        let statement = self.table_create_statement_for(table_name, backend);
+        match backend {
+            SqlBackend::Postgres => statement.to_string(PostgresQueryBuilder),
+            SqlBackend::MySql => statement.to_string(MysqlQueryBuilder),
+            SqlBackend::Sqlite => statement.to_string(SqliteQueryBuilder),
+        }
+    }
+
+    /// The fields in the order their columns are created: fields with an ordinal come
+    /// first sorted by it, the rest follow. Ties keep the order they were declared in.
+    pub fn ordered_fields(&self) -> Vec<&Field> {
+        let mut fields = self.inner().iter().flatten().collect::<Vec<_>>();
+        // sort_by_key is stable, so equal ordinals keep their input order
+        fields.sort_by_key(|field| field.ordinal().unwrap_or(u32::MAX));
+        fields
+    }
+
+    /// Describes the rows of the schema's table as a JSON Schema (draft-07) document,
+    /// so payloads can be validated before being sent. Fields that are not nullable
+    /// are listed as `required`, nullable ones also accept a `null` value.
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+
+        for field in self.ordered_fields() {
+            let mut property = field.field_type().json_schema();
+            if let Some(length) = field.length() {
+                property["maxLength"] = (*length).into();
+            }
+
+            // Any JSON document, with no type to extend, already accepts null
+            if let (true, Some(names)) = (*field.nullable(), property.get("type").cloned()) {
+                property["type"] = match names {
+                    serde_json::Value::Array(mut names) => {
+                        names.push("null".into());
+                        names.into()
+                    }
+                    name => serde_json::json!([name, "null"]),
+                };
+            }
+            properties.insert(field.name().clone(), property);
+
+            if !field.nullable() {
+                required.push(field.name().clone());
+            }
+        }
+
+        serde_json::json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    /// The fields whose type matches `predicate`, in declaration order,
+    /// e.g. `schema.columns_of_type(Type::is_numeric)`.
+    pub fn columns_of_type(&self, predicate: impl Fn(&Type) -> bool) -> Vec<&Field> {
+        self.inner()
+            .iter()
+            .flatten()
+            .filter(|field| predicate(field.field_type()))
+            .collect()
+    }
+
+    /// Estimates the size of a row in the generated table, synthetic `id` column included.
+    pub fn estimate_row_bytes(&self) -> usize {
+        let fields = self
+            .inner()
+            .iter()
+            .flatten()
+            .map(|field| field.field_type().estimated_bytes())
+            .sum::<usize>();
+
+        // A timestamp takes 8 bytes
+        let timestamps = if self.timestamps() { 16 } else { 0 };
+
+        match self.has_synthetic_id() {
+            true => fields + timestamps + self.id_strategy().id_type().estimated_bytes(),
+            false => fields + timestamps,
+        }
+    }
+
+    /// Checks a schema before issuing its DDL, through the same checks a deserialized
+    /// schema goes through: its fields must be valid on their own and have distinct column
+    /// names of at most [MAX_IDEN_LEN] bytes, which collide with neither the synthetic id nor the timestamp columns, and
+    /// the primary key and indexes must be over declared fields.
+    /// A schema declaring no fields is reported last, the deserializer accepts those.
+    pub fn validate(&self) -> Result<(), SchemaValidationError> {
+        use SchemaValidationError as E;
+        let invalid_identifier = |name: &str| {
+            let name = name.to_owned();
+            move |source| E::InvalidIdentifier { name, source }
+        };
+
+        let count = self.inner().iter().flatten().count();
+        if count > MAX_FIELDS {
+            return Err(E::TooManyFields(count));
+        }
+
+        let mut columns = BTreeSet::new();
+        let mut auto_increment = false;
+        for field in self.inner().iter().flatten() {
+            let name = field.name();
+            if name.is_empty() {
+                return Err(E::InvalidIdentifier {
+                    name: name.clone(),
+                    source: IdenError::Empty,
+                });
+            }
+            // Postgres cuts longer names down to their first bytes, so two long names that
+            // only differ past them would collide when the table is created
+            let column = name.to_lowercase();
+            if column.len() > MAX_IDEN_LEN {
+                return Err(E::InvalidIdentifier {
+                    name: name.clone(),
+                    source: IdenError::TooLong(column.len()),
+                });
+            }
+            if !columns.insert(column) {
+                return Err(E::DuplicateField(name.clone()));
+            }
+            if let Err(reason) = field.field_type().check_parameters() {
+                return Err(E::InvalidType {
+                    name: name.clone(),
+                    reason,
+                });
+            }
+            if let Some(reference) = field.references() {
+                IdenString::try_new(reference.table())
+                    .map_err(invalid_identifier(reference.table()))?;
+                IdenString::try_new(reference.column())
+                    .map_err(invalid_identifier(reference.column()))?;
+            }
+            if *field.length() == Some(0) {
+                return Err(E::ZeroLength(name.clone()));
+            }
+            if field.length().is_some() && *field.field_type() != Type::Text {
+                return Err(E::LengthOnNonText(name.clone()));
+            }
+            if *field.non_empty() && *field.field_type() != Type::Text {
+                return Err(E::NonEmptyOnNonText(name.clone()));
+            }
+            if field
+                .check()
+                .as_deref()
+                .is_some_and(|c| !is_contained_check(c))
+            {
+                return Err(E::UnsafeCheck(name.clone()));
+            }
+            if *field.auto_increment() {
+                if *field.nullable() {
+                    return Err(E::NullableAutoIncrement(name.clone()));
+                }
+                if field.field_type().integer_range().is_none() {
+                    return Err(E::NonIntegerAutoIncrement(name.clone()));
+                }
+                if auto_increment {
+                    return Err(E::MultipleAutoIncrement);
+                }
+                auto_increment = true;
+            }
+        }
+
+        IdenString::try_new(self.id_column()).map_err(invalid_identifier(self.id_column()))?;
+        for field in self.inner().iter().flatten() {
+            let column = field.name().to_lowercase();
+            if self.has_synthetic_id() && column == self.id_column() {
+                return Err(E::IdFieldCollision(field.name().clone()));
+            }
+            if self.timestamps() && TIMESTAMP_COLUMNS.contains(&column.as_str()) {
+                return Err(E::TimestampCollision(field.name().clone()));
+            }
+        }
+
+        let is_declared =
+            |column: &String| self.inner().iter().flatten().any(|f| f.name() == column);
+
+        let mut primary_key = BTreeSet::new();
+        for column in self.primary_key() {
+            if !is_declared(column) {
+                return Err(E::UnknownPrimaryKeyColumn(column.clone()));
+            }
+            if !primary_key.insert(column) {
+                return Err(E::RepeatedPrimaryKeyColumn(column.clone()));
+            }
+        }
+
+        let mut index_names = BTreeSet::new();
+        for index in self.indexes() {
+            IdenString::try_new(index.name()).map_err(invalid_identifier(index.name()))?;
+            if !index_names.insert(index.name()) {
+                return Err(E::DuplicateIndex(index.name().clone()));
+            }
+            if index.columns().is_empty() {
+                return Err(E::EmptyIndex(index.name().clone()));
+            }
+            if let Some(column) = index.columns().iter().find(|c| !is_declared(c)) {
+                return Err(E::UnknownIndexColumn {
+                    index: index.name().clone(),
+                    column: column.clone(),
+                });
+            }
+        }
+
+        match self.is_empty() {
+            true => Err(E::Empty),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks the schema against the given limits, catching tables the database
+    /// would refuse (or struggle) to create before any statement is issued.
+    pub fn check_limits(&self, limits: &SchemaLimits) -> Result<(), SchemaLimitError> {
+        // The synthetic id and timestamp columns also count towards the limit
+        let count = self.inner().iter().flatten().count()
+            + usize::from(self.has_synthetic_id())
+            + if self.timestamps() {
+                TIMESTAMP_COLUMNS.len()
+            } else {
+                0
+            };
+        if count > limits.max_columns {
+            return Err(SchemaLimitError::TooManyColumns {
+                count,
+                max: limits.max_columns,
+            });
+        }
+
+        let estimate = self.estimate_row_bytes();
+        if estimate > limits.max_row_bytes {
+            return Err(SchemaLimitError::RowTooWide {
+                estimate,
+                max: limits.max_row_bytes,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks the schema holds nothing `backend` would refuse to create, before issuing
+    /// its DDL. Only Postgres has sequences of their own, MySQL needs an auto_increment
+    /// column to lead the primary key, and SQLite never accepts one in a table level key.
+    pub fn check_backend(&self, backend: SqlBackend) -> Result<(), SchemaBackendError> {
+        let leads_primary_key = |field: &Field| {
+            backend == SqlBackend::MySql && self.primary_key().first() == Some(field.name())
+        };
+
+        match self
+            .inner()
+            .iter()
+            .flatten()
+            .find(|field| *field.auto_increment())
+        {
+            Some(field) if backend != SqlBackend::Postgres && !leads_primary_key(field) => {
+                Err(SchemaBackendError::AutoIncrementOutsidePrimaryKey {
+                    field: field.name().clone(),
+                    backend,
+                })
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Generates a create table statement using Seaquery (part of SeaORM), this statement
     /// is backend agnostic, the translation to a specific flavor of SQL is done with a
     /// QueryBuilder, the query builder _used for testing_ is the
     /// [PostgresQueryBuilder](sea_query::PostgresQueryBuilder).
-    pub fn table_create_statement<'a>(&self, table_name: &'a str) -> TableCreateStatement {
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    pub fn table_create_statement(&self, table_name: &str) -> TableCreateStatement {
+        self.table_create_statement_for(table_name, SqlBackend::default())
+    }
+
+    /// Same as [Schema::table_create_statement], but every identifier is quoted
+    /// the way `backend` expects, whatever query builder ends up rendering it.
+    /// Run [Schema::check_backend] first, some schemas can't be created on every backend.
+    pub fn table_create_statement_for(
+        &self,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> TableCreateStatement {
         // The table create statement is done using a constructor that is builder like.
         let mut statement = Table::create();
         // The iden_str! macro here, allows us to provide a runtime String, as the table name
-        statement.table(iden_str!(table_name));
+        statement.table(QuotedIden::new(iden_str!(table_name), backend));
 
         // Go through each Field in the vec and create a corresponding column for it
-        for entry in self.inner().iter() {
-            if entry.is_none() {
-                continue;
+        for entry in self.ordered_fields() {
+            statement.col(&mut entry.column_def(backend));
+        }
+
+        if self.timestamps() {
+            for column in TIMESTAMP_COLUMNS {
+                statement.col(
+                    ColumnDef::new(QuotedIden::new(iden_str!(column), backend))
+                        .timestamp()
+                        .not_null()
+                        .default(Expr::current_timestamp()),
+                );
             }
-            let entry = entry.as_ref().unwrap();
-            let mut column = ColumnDef::new(iden_str!(entry.name()));
+        }
 
-            entry.nullable().then(|| column.null());
+        for (field, reference) in self
+            .ordered_fields()
+            .into_iter()
+            .filter_map(|field| Some((field, field.references().as_ref()?)))
+        {
+            statement.foreign_key(
+                ForeignKey::create()
+                    .from(
+                        QuotedIden::new(iden_str!(table_name), backend),
+                        QuotedIden::new(iden_str!(field.name()), backend),
+                    )
+                    .to(
+                        QuotedIden::new(iden_str!(reference.table()), backend),
+                        QuotedIden::new(iden_str!(reference.column()), backend),
+                    ),
+            );
+        }
 
-            let col_type = match entry.field_type() {
-                Type::Integer => column.integer(),
-                Type::Float => column.float(),
-                Type::Text => column.text(),
-                Type::Bool => column.boolean(),
-            };
-            statement.col(col_type);
+        if !self.has_synthetic_id() {
+            let mut primary_key = Index::create();
+            for column in self.primary_key() {
+                primary_key.col(QuotedIden::new(iden_str!(column), backend));
+            }
+            return statement.primary_key(&mut primary_key).to_owned();
         }
 
-        let mut table_unique_id = ColumnDef::new(iden_str!("id"));
-        table_unique_id.integer().not_null().auto_increment();
+        statement
+            .col(&mut self.id_strategy().id_column_def(self.id_column(), backend))
+            .to_owned()
+    }
 
-        statement.col(table_unique_id.primary_key()).to_owned()
+    /// Computes the alter statements turning the table of this schema into the table of
+    /// `other`: dropping the columns `other` no longer declares, adding its new columns and
+    /// modifying those whose type changed or that became nullable. Fields are matched by
+    /// name. Table level options (primary key, indexes, id strategy...) are not compared.
+    /// Identifiers are quoted for [SqlBackend::Postgres].
+    pub fn diff(
+        &self,
+        other: &Schema,
+        table_name: &str,
+    ) -> Result<Vec<TableAlterStatement>, SchemaDiffError> {
+        self.diff_for(other, table_name, SqlBackend::default())
+    }
+
+    /// Same as [Schema::diff], but every identifier is quoted the way `backend` expects.
+    /// SQLite can't modify columns, so there a changed column is an error.
+    pub fn diff_for(
+        &self,
+        other: &Schema,
+        table_name: &str,
+        backend: SqlBackend,
+    ) -> Result<Vec<TableAlterStatement>, SchemaDiffError> {
+        fn find<'a>(schema: &'a Schema, field: &Field) -> Option<&'a Field> {
+            schema.inner().iter().flatten().find(|f| *f == field)
+        }
+        let mut statements = Vec::new();
+
+        for field in self.ordered_fields() {
+            if find(other, field).is_none() {
+                statements.push(
+                    Table::alter()
+                        .table(QuotedIden::new(iden_str!(table_name), backend))
+                        .drop_column(QuotedIden::new(iden_str!(field.name()), backend))
+                        .to_owned(),
+                );
+            }
+        }
+
+        for field in other.ordered_fields() {
+            match find(self, field) {
+                None => statements.push(field.add_column_statement(table_name, backend)),
+                Some(current) => {
+                    let retyped = current.column_type(backend) != field.column_type(backend);
+                    // Like on table creation, becoming not nullable writes nothing
+                    let nullability = current.nullable() != field.nullable();
+                    let made_nullable = nullability && *field.nullable();
+                    if !retyped && !made_nullable {
+                        continue;
+                    }
+                    if backend == SqlBackend::Sqlite {
+                        return Err(SchemaDiffError::UnsupportedModify(field.name().clone()));
+                    }
+                    statements.push(field.modify_column(table_name, backend, nullability));
+                }
+            }
+        }
+
+        Ok(statements)
+    }
+}
+
+/// Builds a [Schema] in Rust code, without going through its JSON declaration, e.g.
+/// `SchemaBuilder::new().field("device", Type::Text).unique().build()`.
+/// Field options, like [SchemaBuilder::nullable], apply to the last added field.
+#[derive(Debug, Default)]
+pub struct SchemaBuilder {
+    schema: Schema,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a non-nullable field of the given type.
+    pub fn field(mut self, name: &str, field_type: Type) -> Self {
+        self.schema.fields.push(Some(Field {
+            name: name.into(),
+            field_type,
+            nullable: false,
+            ordinal: None,
+            length: None,
+            auto_increment: false,
+            references: None,
+            non_empty: false,
+            check: None,
+            unique: false,
+        }));
+        self
+    }
+
+    /// Makes the last added field nullable.
+    pub fn nullable(self) -> Self {
+        self.with_last_field(|field| field.nullable = true)
+    }
+
+    /// Adds a unique constraint to the last added field.
+    pub fn unique(self) -> Self {
+        self.with_last_field(|field| field.unique = true)
+    }
+
+    fn with_last_field(mut self, option: impl FnOnce(&mut Field)) -> Self {
+        if let Some(Some(field)) = self.schema.fields.last_mut() {
+            option(field);
+        }
+        self
+    }
+
+    /// Finishes the schema, checking it with [Schema::validate].
+    pub fn build(self) -> Result<Schema, SchemaValidationError> {
+        self.schema.validate()?;
+        Ok(self.schema)
     }
 }
 
 // Start section --- Custom serde impls
 
-/// We always expect the Schema to be a sequence (array) of fields
+/// Deserializes the element type of an array, given either as the name of a type without
+/// parameters, `"integer"`, or as a full type declaration, `{"type": "decimal", ...}`.
+fn deserialize_array_items<'de, D>(deserializer: D) -> Result<Box<Type>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let declaration = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(name) => serde_json::json!({ "type": name }),
+        declaration => declaration,
+    };
+    let items = deserialize_field_type(declaration).map_err(serde::de::Error::custom)?;
+    Ok(Box::new(items))
+}
+
+/// Serializes the element type of an array by its name, unless it has parameters.
+fn serialize_array_items<S>(items: &Type, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let declaration = TaggedType::serialize(items, serde_json::value::Serializer);
+    match declaration.map_err(serde::ser::Error::custom)? {
+        serde_json::Value::Object(declaration) if declaration.len() == 1 => {
+            declaration["type"].serialize(serializer)
+        }
+        declaration => declaration.serialize(serializer),
+    }
+}
+
+/// Deserializes a [Field]'s type, rejecting parameters the database would refuse.
+fn deserialize_field_type<'de, D>(deserializer: D) -> Result<Type, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let field_type = TaggedType::deserialize(deserializer)?;
+    field_type
+        .check_parameters()
+        .map_err(serde::de::Error::custom)?;
+    Ok(field_type)
+}
+
+/// We expect the Schema to be either a sequence (array) of fields,
+/// or a map with the fields and the table level options, see [Schema].
 impl<'de> Deserialize<'de> for Schema {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_seq(SchemaVisitor)
+        SchemaSeed(IdentifierCase::default()).deserialize(deserializer)
+    }
+}
+
+/// Deserializes a [Schema] applying the given [IdentifierCase] policy to its names.
+pub struct SchemaSeed(pub IdentifierCase);
+
+impl<'de> DeserializeSeed<'de> for SchemaSeed {
+    type Value = Schema;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SchemaVisitor { case: self.0 })
+    }
+}
+
+/// Applies the case policy to the field names of a Schema, and to the names referencing them
+fn apply_identifier_case<E>(schema: &mut Schema, case: IdentifierCase) -> Result<(), E>
+where
+    E: serde::de::Error,
+{
+    for field in schema.fields.iter_mut().flatten() {
+        field.name = case.apply(&field.name).map_err(E::custom)?;
+    }
+
+    let index_columns = schema.indexes.iter_mut().flat_map(|i| i.columns.iter_mut());
+    let id_column = schema.id_column.iter_mut();
+    for column in schema
+        .primary_key
+        .iter_mut()
+        .chain(index_columns)
+        .chain(id_column)
+    {
+        *column = case.apply(column).map_err(E::custom)?;
+    }
+
+    Ok(())
+}
+
+/// The object form of a Schema declaration
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaDeclaration {
+    fields: FieldList,
+    #[serde(default)]
+    primary_key: Vec<String>,
+    #[serde(default)]
+    indexes: Vec<IndexSpec>,
+    #[serde(default)]
+    id_strategy: IdStrategy,
+    #[serde(default)]
+    id_field: IdFieldPolicy,
+    #[serde(default)]
+    id_column: Option<String>,
+    #[serde(default)]
+    timestamps: bool,
+}
+
+/// The list of fields of a Schema, always a sequence (array) of fields
+struct FieldList(Vec<Option<Field>>);
+
+impl<'de> Deserialize<'de> for FieldList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(FieldListVisitor)
+    }
+}
+
+/// Makes a declared field colliding with the synthetic primary key column the primary key,
+/// when the policy asks for it. Otherwise the collision is left to [Schema::validate].
+fn apply_id_field_policy(schema: &mut Schema, policy: IdFieldPolicy) {
+    let id_field = schema
+        .inner()
+        .iter()
+        .flatten()
+        .find(|field| field.name().to_lowercase() == schema.id_column());
+
+    if let (Some(field), true, IdFieldPolicy::PrimaryKey) =
+        (id_field, schema.has_synthetic_id(), policy)
+    {
+        schema.primary_key = vec![field.name().clone()];
+    }
+}
+
+/// Runs [Schema::validate] over a deserialized schema, which unlike a constructed one
+/// may declare no fields, see [Schema::is_empty].
+fn validate_declaration<E>(schema: Schema) -> Result<Schema, E>
+where
+    E: serde::de::Error,
+{
+    match schema.validate() {
+        Ok(()) | Err(SchemaValidationError::Empty) => Ok(schema),
+        Err(err) => Err(E::custom(err)),
     }
 }
 
 /// The actual behaviour for deserializing a Schema using serde
-struct SchemaVisitor;
+struct SchemaVisitor {
+    case: IdentifierCase,
+}
 
 impl<'de> Visitor<'de> for SchemaVisitor {
     type Value = Schema;
@@ -218,29 +2018,111 @@ impl<'de> Visitor<'de> for SchemaVisitor {
         formatter.write_str("an invalid Schema declaration.")
     }
 
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut schema = Schema {
+            fields: FieldListVisitor.visit_seq(seq)?.0,
+            ..Default::default()
+        };
+        apply_identifier_case(&mut schema, self.case)?;
+        apply_id_field_policy(&mut schema, IdFieldPolicy::default());
+
+        validate_declaration(schema)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let declaration =
+            SchemaDeclaration::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+        let mut schema = Schema {
+            fields: declaration.fields.0,
+            primary_key: declaration.primary_key,
+            indexes: declaration.indexes,
+            id_strategy: declaration.id_strategy,
+            id_column: declaration.id_column,
+            timestamps: declaration.timestamps,
+        };
+        apply_identifier_case(&mut schema, self.case)?;
+        apply_id_field_policy(&mut schema, declaration.id_field);
+
+        validate_declaration(schema)
+    }
+}
+
+/// Whether a raw check expression stays within its `CHECK (...)` clause: it is not blank,
+/// every parenthesis it opens and quote it starts is closed, and it holds no statement
+/// separator or comment. Parentheses within quotes, `'...'` literals or `"..."` and
+/// `` `...` `` identifiers, don't count, a quote being escaped by doubling it.
+/// Backslashes and dollar signs are rejected outright, as MySQL escapes and Postgres
+/// dollar quoting would make the database read quotes differently than this scan.
+fn is_contained_check(check: &str) -> bool {
+    let mut depth = 0usize;
+    let mut chars = check.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => loop {
+                match chars.next() {
+                    None | Some('\\') => return false,
+                    Some(q) if q == c && chars.next_if_eq(&c).is_none() => break,
+                    Some(_) => {}
+                }
+            },
+            '(' => depth += 1,
+            ')' => match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => return false,
+            },
+            ';' | '#' | '$' | '\\' => return false,
+            '-' if chars.peek() == Some(&'-') => return false,
+            '/' if chars.peek() == Some(&'*') => return false,
+            _ => {}
+        }
+    }
+
+    depth == 0 && !check.trim().is_empty()
+}
+
+/// The actual behaviour for deserializing the fields of a Schema using serde
+struct FieldListVisitor;
+
+impl<'de> Visitor<'de> for FieldListVisitor {
+    type Value = FieldList;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an invalid Field list.")
+    }
+
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
     where
         A: serde::de::SeqAccess<'de>,
     {
-        let mut existing = BTreeSet::<String>::new();
-        let mut schema = Schema::default();
-        let mut i = 0;
+        let mut fields = Vec::new();
 
-        while let Ok(Some(entry)) = seq.next_element::<Field>() {
-            if existing.contains(&entry.name) {
-                Err(serde::de::Error::duplicate_field("Duplicate Field"))?;
-            };
-            existing.insert(entry.name.clone());
-            schema.inner_mut().get(i).replace(&mut Some(entry));
-            i += 1;
+        while let Some(entry) = seq.next_element::<Field>()? {
+            // Checked while reading, so an oversized list is not held in memory. The fields
+            // themselves are checked with the rest of the schema, see [Schema::validate]
+            if fields.len() == MAX_FIELDS {
+                Err(serde::de::Error::invalid_length(
+                    MAX_FIELDS + 1,
+                    &format!("a schema with at most {MAX_FIELDS} fields").as_str(),
+                ))?;
+            }
+            fields.push(Some(entry));
         }
 
-        Ok(schema)
+        Ok(FieldList(fields))
     }
 }
 
 /// The actual behaviour for deserializing a LiveSchema using serde
-struct LiveSchemaVisitor;
+struct LiveSchemaVisitor {
+    case: IdentifierCase,
+    max_json_depth: usize,
+}
 
 impl<'de> Visitor<'de> for LiveSchemaVisitor {
     type Value = LiveSchema;
@@ -255,23 +2137,55 @@ impl<'de> Visitor<'de> for LiveSchemaVisitor {
     {
         let field_count_guess = map.size_hint().unwrap_or(1);
         let mut live_schema = LiveSchema::new(field_count_guess);
+        let mut columns = BTreeSet::<String>::new();
+
+        while let Some((key, value)) = map.next_entry::<String, serde_json::Value>()? {
+            let name = self.case.apply(&key).map_err(serde::de::Error::custom)?;
+            if !columns.insert(name.to_lowercase()) {
+                Err(serde::de::Error::custom(format!(
+                    "field {name:?} has the same column name as another field"
+                )))?;
+            }
+            // Every live table gets the synthetic id column as its primary key
+            if name.to_lowercase() == DEFAULT_ID_COLUMN {
+                Err(serde::de::Error::custom(format!(
+                    "field {name:?} collides with the synthetic id primary key, rename it"
+                )))?;
+            }
+
+            if json_depth(&value) > self.max_json_depth {
+                Err(serde::de::Error::custom(format!(
+                    "field {name:?} nests its JSON value deeper than {} levels",
+                    self.max_json_depth
+                )))?;
+            }
 
-        while let Some((key, value)) = map.next_entry()? {
-            let value: serde_json::Value = value;
             let field = Field {
-                name: key,
-                field_type: Type::try_from(&value).map_err(|_| {
-                    serde::de::Error::invalid_type(
-                        Unexpected::Other("unimplemented conversion for given type"),
-                        &self,
-                    )
-                })?,
+                name,
+                // Type inference is shared with every other JSON value conversion
+                field_type: Type::try_from(&value).map_err(serde::de::Error::custom)?,
                 nullable: false,
+                ordinal: None,
+                length: None,
+                auto_increment: false,
+                references: None,
+                non_empty: false,
+                check: None,
+                unique: false,
             };
 
             live_schema.inner_mut().push(Some((field, value)));
         }
 
+        // Arrays sent as objects, e.g. {"0": 1, "1": 2}, would become columns named "0", "1"...
+        let is_numeric = |name: &String| name.chars().all(|c| c.is_ascii_digit());
+        if !columns.is_empty() && columns.iter().all(is_numeric) {
+            Err(serde::de::Error::custom(
+                "every key of the object is a number, it looks like an array sent as an object, \
+                send an object with named fields instead",
+            ))?;
+        }
+
         live_schema.0.shrink_to_fit();
         Ok(live_schema)
     }
@@ -282,7 +2196,39 @@ impl<'de> Deserialize<'de> for LiveSchema {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_map(LiveSchemaVisitor)
+        LiveSchemaSeed::new(IdentifierCase::default()).deserialize(deserializer)
+    }
+}
+
+/// Deserializes a [LiveSchema] applying the given [IdentifierCase] policy to its names.
+/// Values nesting objects and arrays deeper than `max_json_depth` are rejected, keeping
+/// hostile payloads from exhausting the stack of whatever processes them next.
+pub struct LiveSchemaSeed {
+    pub case: IdentifierCase,
+    pub max_json_depth: usize,
+}
+
+impl LiveSchemaSeed {
+    /// A seed with the given case policy, and [MAX_JSON_DEPTH] as the depth limit.
+    pub fn new(case: IdentifierCase) -> Self {
+        Self {
+            case,
+            max_json_depth: MAX_JSON_DEPTH,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for LiveSchemaSeed {
+    type Value = LiveSchema;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(LiveSchemaVisitor {
+            case: self.case,
+            max_json_depth: self.max_json_depth,
+        })
     }
 }
 
@@ -1,7 +1,28 @@
-use sea_query::PostgresQueryBuilder;
+use sea_query::{MysqlQueryBuilder, PostgresQueryBuilder, Value};
+use serde::de::DeserializeSeed;
 use serde_json::json;
 
-use crate::{Field, LiveSchema, Schema, Type};
+use crate::{
+    BatchError, ConflictStrategy, Field, IdenError, IdenString, IdentifierCase, LiveSchema,
+    LiveSchemaSeed, Schema, SchemaBackendError, SchemaBuilder, SchemaDiffError, SchemaLimitError,
+    SchemaLimits, SchemaSeed, SchemaValidationError, SqlBackend, Type, TypeErrors,
+    MAX_DECIMAL_PRECISION, MAX_FIELDS, MAX_IDEN_LEN, MAX_JSON_DEPTH,
+};
+
+fn field(name: &str, field_type: Type, nullable: bool) -> Field {
+    Field {
+        name: name.into(),
+        field_type,
+        nullable,
+        ordinal: None,
+        length: None,
+        auto_increment: false,
+        references: None,
+        non_empty: false,
+        check: None,
+        unique: false,
+    }
+}
 
 #[test]
 fn wont_serialize_repeated_fields() {
@@ -48,42 +69,23 @@ fn deserialize_static_schema() {
     ]);
 
     let vec = [
-        Field {
-            name: "temperature".into(),
-            field_type: Type::Integer,
-            nullable: true,
-        },
-        Field {
-            name: "device".into(),
-            field_type: Type::Text,
-            nullable: false,
-        },
+        field("temperature", Type::Integer, true),
+        field("device", Type::Text, false),
     ];
 
     let js_vec = serde_json::from_value::<[Field; 2]>(_json);
     for (l, r) in vec.iter().zip(js_vec.iter().flatten()) {
-        if l.ne(r) {
-            assert!(false)
-        }
+        assert!(l.eq(r))
     }
-    assert!(true)
 }
 
 #[test]
 fn build_sql_from_schema() {
-    let x = Field {
-        name: "temperature".into(),
-        field_type: Type::Integer,
-        nullable: true,
-    };
-    let y = Field {
-        name: "active".into(),
-        field_type: Type::Bool,
-        nullable: true,
-    };
+    let x = field("temperature", Type::Integer, true);
+    let y = field("active", Type::Bool, true);
     let mut schema = Schema::default();
-    schema.0.push(Some(x));
-    schema.0.push(Some(y));
+    schema.fields.push(Some(x));
+    schema.fields.push(Some(y));
 
     dbg!(&schema);
 
@@ -92,7 +94,7 @@ fn build_sql_from_schema() {
         .to_string(PostgresQueryBuilder)
         .to_lowercase();
 
-    let table = vec![
+    let table = [
         r#"create table "test_t" ("#,
         r#""temperature" integer null,"#,
         r#""active" bool null,"#,
@@ -114,32 +116,20 @@ fn parse_live_schema_from_json() {
 
     let mut want = LiveSchema::new(3);
     want.0.push(Some((
-        Field {
-            name: "temperature".into(),
-            field_type: Type::Float,
-            nullable: false,
-        },
+        field("temperature", Type::Float, false),
         serde_json::Value::from(23.2),
     )));
     want.0.push(Some((
-        Field {
-            name: "active".into(),
-            field_type: Type::Bool,
-            nullable: false,
-        },
+        field("active", Type::Bool, false),
         serde_json::Value::from(false),
     )));
     want.0.push(Some((
-        Field {
-            name: "device".into(),
-            field_type: Type::Text,
-            nullable: false,
-        },
+        field("device", Type::Text, false),
         serde_json::Value::from("AmberRoomTemp"),
     )));
 
     let have: LiveSchema = serde_json::from_value(json).unwrap();
-    assert!(have.0.get(0).unwrap().eq(have.0.get(0).unwrap()));
+    assert!(have.0.first().unwrap().eq(have.0.first().unwrap()));
     assert!(have.0.get(1).unwrap().eq(have.0.get(1).unwrap()));
     assert!(have.0.get(2).unwrap().eq(have.0.get(2).unwrap()));
 }
@@ -162,7 +152,8 @@ fn create_table_sql_from_live_json_schema() {
         .to_string(PostgresQueryBuilder)
         .to_lowercase();
 
-    let table = vec![
+    // Columns are sorted by name, whatever order the JSON map yields them in
+    let table = [
         r#"create table "test_t" ("#,
         r#""device" text,"#,
         r#""temperature" real,"#,
@@ -173,3 +164,2175 @@ fn create_table_sql_from_live_json_schema() {
 
     assert_eq!(sql, table)
 }
+
+#[test]
+fn iden_string_accepts_valid_identifiers() {
+    assert!(IdenString::try_new("temperature").is_ok());
+    assert!(IdenString::try_new("_room_42").is_ok());
+}
+
+#[test]
+fn iden_string_rejects_injection_attempts() {
+    let attempts = [
+        r#"foo"; DROP TABLE templates; --"#,
+        r#"foo" ("#,
+        "foo; select 1",
+        "1foo",
+        "Foo",
+    ];
+
+    for attempt in attempts {
+        assert_eq!(
+            IdenString::try_new(attempt).err(),
+            Some(IdenError::InvalidCharacters(attempt.into()))
+        );
+    }
+    assert_eq!(IdenString::try_new("").err(), Some(IdenError::Empty));
+}
+
+#[test]
+fn iden_string_rejects_overly_long_names() {
+    let at_limit = "a".repeat(MAX_IDEN_LEN);
+    let over_limit = "a".repeat(MAX_IDEN_LEN + 1);
+
+    assert!(IdenString::try_new(&at_limit).is_ok());
+    assert_eq!(
+        IdenString::try_new(&over_limit).err(),
+        Some(IdenError::TooLong(MAX_IDEN_LEN + 1))
+    );
+
+    // Non-ASCII names are invalid, whatever their length in bytes
+    let non_ascii = "é".repeat(40);
+    assert_eq!(
+        IdenString::try_new(&non_ascii).err(),
+        Some(IdenError::InvalidCharacters(non_ascii))
+    );
+}
+
+#[test]
+fn reserved_word_columns_are_quoted_per_backend() {
+    let mut schema = Schema::default();
+    schema
+        .fields
+        .push(Some(field("order", Type::Integer, false)));
+
+    let postgres = schema
+        .table_create_statement_for("select", SqlBackend::Postgres)
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+    let postgres_want = [
+        r#"create table "select" ("#,
+        r#""order" integer,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(postgres, postgres_want);
+
+    let mysql = schema
+        .table_create_statement_for("select", SqlBackend::MySql)
+        .to_string(MysqlQueryBuilder)
+        .to_lowercase();
+    let mysql_want = [
+        r#"create table `select` ("#,
+        r#"`order` int,"#,
+        r#"`id` int not null auto_increment primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(mysql, mysql_want);
+}
+
+#[test]
+fn quoting_follows_the_backend_not_the_builder() {
+    let mut schema = Schema::default();
+    schema.fields.push(Some(field("we`ird", Type::Text, false)));
+
+    let sql = schema
+        .table_create_statement_for("t", SqlBackend::MySql)
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert!(sql.starts_with("create table `t` ( `we``ird` text,"));
+}
+
+#[test]
+fn insert_statement_from_live_schema() {
+    let mut schema = LiveSchema::new(3);
+    schema.0.push(Some((
+        field("temperature", Type::Float, false),
+        serde_json::Value::from(23.2),
+    )));
+    schema.0.push(None);
+    schema.0.push(Some((
+        field("device", Type::Text, false),
+        serde_json::Value::from("x"),
+    )));
+
+    let sql = schema
+        .insert_statement("t")
+        .unwrap()
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert_eq!(
+        sql,
+        r#"insert into "t" ("temperature", "device") values (23.2, 'x')"#
+    )
+}
+
+#[test]
+fn insert_statement_rejects_incompatible_values() {
+    let mut schema = LiveSchema::new(1);
+    schema.0.push(Some((
+        field("temperature", Type::Integer, false),
+        serde_json::Value::from("hot"),
+    )));
+
+    assert!(matches!(
+        schema.insert_statement("t"),
+        Err(TypeErrors::IncompatibleValue(_, Type::Integer))
+    ));
+}
+
+#[test]
+fn insert_statement_rejects_empty_live_schema() {
+    let schema: LiveSchema = serde_json::from_str("{}").unwrap();
+
+    assert!(matches!(
+        schema.insert_statement("t"),
+        Err(TypeErrors::NoValues)
+    ));
+}
+
+#[test]
+fn estimate_row_bytes_of_schema() {
+    let mut schema = Schema::default();
+    schema
+        .fields
+        .push(Some(field("temperature", Type::Float, false)));
+    schema.fields.push(Some(field("device", Type::Text, false)));
+
+    // float + text + synthetic integer id
+    assert_eq!(schema.estimate_row_bytes(), 4 + 18 + 4);
+    assert!(schema.check_limits(&SchemaLimits::default()).is_ok());
+}
+
+#[test]
+fn schema_over_column_limit_is_rejected() {
+    let mut schema = Schema::default();
+    for i in 0..10 {
+        schema
+            .fields
+            .push(Some(field(&format!("reading_{i}"), Type::Bool, false)));
+    }
+
+    let limits = SchemaLimits {
+        max_columns: 10,
+        ..Default::default()
+    };
+    let err = schema.check_limits(&limits).unwrap_err();
+
+    assert_eq!(err, SchemaLimitError::TooManyColumns { count: 11, max: 10 });
+    assert_eq!(
+        err.to_string(),
+        "The schema declares 11 columns, the maximum allowed is 10"
+    );
+}
+
+#[test]
+fn schema_over_row_size_limit_is_rejected() {
+    let mut schema = Schema::default();
+    schema.fields.push(Some(field("device", Type::Text, false)));
+
+    let limits = SchemaLimits {
+        max_row_bytes: 16,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        schema.check_limits(&limits),
+        Err(SchemaLimitError::RowTooWide {
+            estimate: 22,
+            max: 16
+        })
+    );
+}
+
+#[test]
+fn decimal_field_from_json() {
+    let json = json!([
+        {
+            "name": "price",
+            "type": "decimal",
+            "precision": 10,
+            "scale": 2
+        }
+    ]);
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(
+        schema.fields[0].as_ref().unwrap().field_type,
+        Type::Decimal {
+            precision: 10,
+            scale: 2
+        }
+    );
+
+    let sql = schema
+        .table_create_statement("products")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "products" ("#,
+        r#""price" decimal(10, 2),"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn wont_deserialize_decimal_with_scale_over_precision() {
+    let json = json!([
+        {
+            "name": "price",
+            "type": "decimal",
+            "precision": 2,
+            "scale": 10
+        }
+    ]);
+
+    assert!(serde_json::from_value::<Schema>(json).is_err());
+
+    for precision in [0, MAX_DECIMAL_PRECISION + 1] {
+        let json = json!([
+            { "name": "price", "type": "decimal", "precision": precision, "scale": 0 }
+        ]);
+        assert!(serde_json::from_value::<Schema>(json).is_err());
+    }
+}
+
+#[test]
+fn decimal_values_are_kept_exact() {
+    let price = field(
+        "price",
+        Type::Decimal {
+            precision: 30,
+            scale: 10,
+        },
+        false,
+    );
+
+    // More digits than a float holds
+    let exact = "12345678901234567890.0123456789";
+    assert_eq!(price.sql_value(&json!(exact)).unwrap(), Value::from(exact));
+    assert_eq!(price.sql_value(&json!(12.5)).unwrap(), Value::from("12.5"));
+    assert_eq!(
+        price.sql_value(&json!("-.5e-3")).unwrap(),
+        Value::from("-.5e-3")
+    );
+
+    for invalid in [
+        json!("12,5"),
+        json!("."),
+        json!("1e"),
+        json!("NaN"),
+        json!(true),
+    ] {
+        assert!(matches!(
+            price.sql_value(&invalid),
+            Err(TypeErrors::IncompatibleValue(..))
+        ));
+    }
+
+    let prices = field(
+        "prices",
+        Type::Array {
+            items: Box::new(price.field_type().clone()),
+        },
+        false,
+    );
+    assert_eq!(
+        prices.sql_value(&json!([exact, 1.25])).unwrap(),
+        Value::from(format!(r#"{{"{exact}","1.25"}}"#))
+    );
+}
+
+#[test]
+fn ordinals_reorder_schema_columns() {
+    let json = json!([
+        { "name": "device", "type": "text" },
+        { "name": "humidity", "type": "float", "ordinal": 2 },
+        { "name": "temperature", "type": "float", "ordinal": 1 },
+        { "name": "active", "type": "bool", "ordinal": 2 },
+    ]);
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("test_t")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""temperature" real,"#,
+        r#""humidity" real,"#,
+        r#""active" bool,"#,
+        r#""device" text,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn schema_to_sql_for_every_backend() {
+    let mut schema = Schema::default();
+    schema
+        .fields
+        .push(Some(field("temperature", Type::Float, true)));
+    schema.fields.push(Some(field("device", Type::Text, false)));
+
+    let postgres = [
+        r#"create table "test_t" ("#,
+        r#""temperature" real null,"#,
+        r#""device" text,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    let mysql = [
+        r#"create table `test_t` ("#,
+        r#"`temperature` float null,"#,
+        r#"`device` text,"#,
+        r#"`id` int not null auto_increment primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    let sqlite = [
+        r#"create table "test_t" ("#,
+        r#""temperature" real null,"#,
+        r#""device" text,"#,
+        r#""id" integer not null primary key autoincrement"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    let sql = |backend| schema.to_sql("test_t", backend).to_lowercase();
+    assert_eq!(sql(SqlBackend::Postgres), postgres);
+    assert_eq!(sql(SqlBackend::MySql), mysql);
+    assert_eq!(sql(SqlBackend::Sqlite), sqlite);
+}
+
+#[test]
+fn wont_deserialize_schema_over_max_fields() {
+    let fields = |count: usize| {
+        (0..count)
+            .map(|i| json!({ "name": format!("reading_{i}"), "type": "integer" }))
+            .collect::<Vec<_>>()
+    };
+
+    assert!(serde_json::from_value::<Schema>(json!(fields(MAX_FIELDS))).is_ok());
+
+    let err = serde_json::from_value::<Schema>(json!(fields(MAX_FIELDS + 1))).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invalid length {}, expected a schema with at most {MAX_FIELDS} fields",
+            MAX_FIELDS + 1
+        )
+    );
+}
+
+#[test]
+fn text_field_with_length_is_a_varchar() {
+    let json = json!([
+        { "name": "code", "type": "text", "length": 16 },
+        { "name": "notes", "type": "text" },
+    ]);
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("test_t")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""code" varchar(16),"#,
+        r#""notes" text,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn wont_deserialize_invalid_lengths() {
+    let json = json!([{ "name": "code", "type": "text", "length": 0 }]);
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"length of field "code" must be greater than 0"#
+    );
+
+    let json = json!([{ "name": "count", "type": "integer", "length": 16 }]);
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"field "count" with a length must be text"#
+    );
+}
+
+#[test]
+fn type_inference_from_json_values() {
+    let matrix = [
+        (json!(true), Type::Bool),
+        (json!(42), Type::Integer),
+        (json!(-42), Type::Integer),
+        (json!(23.2), Type::Float),
+        (json!("Tmp0233AO"), Type::Text),
+    ];
+
+    for (value, want) in matrix {
+        assert_eq!(Type::try_from(&value).unwrap(), want);
+
+        let live = serde_json::from_value::<LiveSchema>(json!({ "reading": value })).unwrap();
+        let (field, _) = live.0[0].as_ref().unwrap();
+        assert_eq!(field.field_type, want);
+    }
+
+    assert!(matches!(
+        Type::try_from(&json!(null)),
+        Err(TypeErrors::UnimplementedConversion)
+    ));
+    let err = serde_json::from_value::<LiveSchema>(json!({ "reading": null })).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        TypeErrors::UnimplementedConversion.to_string()
+    );
+}
+
+#[test]
+fn composite_primary_key_replaces_synthetic_id() {
+    let json = json!({
+        "fields": [
+            { "name": "device_id", "type": "integer" },
+            { "name": "room_id", "type": "integer" },
+            { "name": "since", "type": "text", "nullable": true },
+        ],
+        "primary_key": ["device_id", "room_id"]
+    });
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("device_rooms")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "device_rooms" ("#,
+        r#""device_id" integer,"#,
+        r#""room_id" integer,"#,
+        r#""since" text null,"#,
+        r#"primary key ("device_id", "room_id")"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn wont_deserialize_primary_key_over_unknown_column() {
+    let json = json!({
+        "fields": [{ "name": "device_id", "type": "integer" }],
+        "primary_key": ["device_id", "room_id"]
+    });
+
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"primary key column "room_id" is not a declared field"#
+    );
+}
+
+#[test]
+fn wont_deserialize_primary_key_with_repeated_column() {
+    let json = json!({
+        "fields": [{ "name": "device_id", "type": "integer" }],
+        "primary_key": ["device_id", "device_id"]
+    });
+
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"primary key column "device_id" is listed more than once"#
+    );
+}
+
+#[test]
+fn auto_increment_column_outside_primary_key() {
+    let json = json!([
+        { "name": "sequence", "type": "integer", "auto_increment": true },
+        { "name": "device", "type": "text" },
+    ]);
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("test_t")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""sequence" serial,"#,
+        r#""device" text,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn wont_deserialize_invalid_auto_increment_fields() {
+    let not_integer = json!([
+        { "name": "device", "type": "text", "auto_increment": true },
+    ]);
+    let err = serde_json::from_value::<Schema>(not_integer).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"auto_increment field "device" must be an integer"#
+    );
+
+    let two_sequences = json!([
+        { "name": "sequence", "type": "integer", "auto_increment": true },
+        { "name": "counter", "type": "integer", "auto_increment": true },
+    ]);
+    let err = serde_json::from_value::<Schema>(two_sequences).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "only one field per schema can be auto_increment"
+    );
+
+    let nullable = json!([
+        { "name": "sequence", "type": "integer", "auto_increment": true, "nullable": true },
+    ]);
+    let err = serde_json::from_value::<Schema>(nullable).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"auto_increment field "sequence" can not be nullable"#
+    );
+}
+
+#[test]
+fn auto_increment_outside_primary_key_per_backend() {
+    let json = json!([{ "name": "sequence", "type": "integer", "auto_increment": true }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+
+    assert!(schema.check_backend(SqlBackend::Postgres).is_ok());
+    for backend in [SqlBackend::MySql, SqlBackend::Sqlite] {
+        assert_eq!(
+            schema.check_backend(backend),
+            Err(SchemaBackendError::AutoIncrementOutsidePrimaryKey {
+                field: "sequence".into(),
+                backend,
+            })
+        );
+    }
+
+    let json = json!({
+        "fields": [{ "name": "sequence", "type": "integer", "auto_increment": true }],
+        "primary_key": ["sequence"]
+    });
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+
+    assert!(schema.check_backend(SqlBackend::MySql).is_ok());
+    assert!(schema.check_backend(SqlBackend::Sqlite).is_err());
+}
+
+#[test]
+fn unique_multi_column_index_from_schema() {
+    let json = json!({
+        "fields": [
+            { "name": "device", "type": "text" },
+            { "name": "room", "type": "text" },
+            { "name": "temperature", "type": "float" },
+        ],
+        "indexes": [
+            { "name": "device_room_idx", "columns": ["device", "room"], "unique": true },
+            { "name": "temperature_idx", "columns": ["temperature"] },
+        ]
+    });
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let (table, indexes) = schema.create_statements("readings");
+
+    assert!(table
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase()
+        .starts_with(r#"create table "readings" ("#));
+
+    let indexes = indexes
+        .iter()
+        .map(|index| index.to_string(PostgresQueryBuilder).to_lowercase())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        indexes,
+        [
+            r#"create unique index "device_room_idx" on "readings" ("device", "room")"#,
+            r#"create index "temperature_idx" on "readings" ("temperature")"#,
+        ]
+    );
+}
+
+#[test]
+fn wont_deserialize_index_over_unknown_column() {
+    let json = json!({
+        "fields": [{ "name": "device", "type": "text" }],
+        "indexes": [{ "name": "room_idx", "columns": ["room"] }]
+    });
+
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"index "room_idx" column "room" is not a declared field"#
+    );
+}
+
+#[test]
+fn uuid_field_and_id_strategies() {
+    let fields = json!([
+        { "name": "device", "type": "uuid" },
+    ]);
+
+    let serial = serde_json::from_value::<Schema>(json!({
+        "fields": fields,
+        "id_strategy": "serial"
+    }))
+    .unwrap();
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""device" uuid,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(
+        serial.to_sql("test_t", SqlBackend::Postgres).to_lowercase(),
+        table
+    );
+
+    let uuid = serde_json::from_value::<Schema>(json!({
+        "fields": fields,
+        "id_strategy": "uuid"
+    }))
+    .unwrap();
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""device" uuid,"#,
+        r#""id" uuid not null default gen_random_uuid() primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(
+        uuid.to_sql("test_t", SqlBackend::Postgres).to_lowercase(),
+        table
+    );
+
+    let mysql = uuid.to_sql("test_t", SqlBackend::MySql).to_lowercase();
+    assert!(
+        mysql.ends_with("`id` char(36) not null default (uuid()) primary key )"),
+        "{mysql}"
+    );
+
+    let sqlite = uuid.to_sql("test_t", SqlBackend::Sqlite).to_lowercase();
+    assert!(!sqlite.contains("gen_random_uuid"), "{sqlite}");
+    assert!(
+        sqlite.contains("default (lower(hex(randomblob(4)))"),
+        "{sqlite}"
+    );
+}
+
+#[test]
+fn uuid_strings_are_inferred_as_uuid() {
+    let uuid = json!("67e55044-10b1-426f-9247-bb680e5fe0c8");
+    assert_eq!(Type::try_from(&uuid).unwrap(), Type::Uuid);
+
+    let not_uuid = json!("67e55044-10b1-426f-9247-bb680e5fe0cX");
+    assert_eq!(Type::try_from(&not_uuid).unwrap(), Type::Text);
+}
+
+#[test]
+fn interval_field_to_sql() {
+    let mut schema = Schema::default();
+    schema
+        .fields
+        .push(Some(field("uptime", Type::Interval, false)));
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""uptime" interval,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(
+        schema.to_sql("test_t", SqlBackend::Postgres).to_lowercase(),
+        table
+    );
+
+    let json = json!([{ "name": "uptime", "type": "interval" }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(
+        schema.fields[0].as_ref().unwrap().field_type,
+        Type::Interval
+    );
+}
+
+#[test]
+fn iso8601_durations_are_inferred_as_interval() {
+    for duration in ["PT1H", "P1Y2M10DT2H30M", "P2W", "PT0.5S", "P1D"] {
+        assert_eq!(Type::try_from(&json!(duration)).unwrap(), Type::Interval);
+    }
+
+    for text in ["1 hour", "P", "PT", "P1H", "PT1M1H", "P1.5DT1H", "Pizza"] {
+        assert_eq!(Type::try_from(&json!(text)).unwrap(), Type::Text);
+    }
+}
+
+#[test]
+fn live_schema_columns_are_sorted_by_name() {
+    let mut schema = LiveSchema::new(3);
+    schema.0.push(Some((
+        field("temperature", Type::Float, false),
+        json!(23.2),
+    )));
+    schema
+        .0
+        .push(Some((field("active", Type::Bool, false), json!(true))));
+    schema
+        .0
+        .push(Some((field("device", Type::Text, false), json!("x"))));
+
+    let sql = schema
+        .table_create_statement("test_t")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""active" bool,"#,
+        r#""device" text,"#,
+        r#""temperature" real,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn identifier_case_lower_policy() {
+    let json = json!([{ "name": "DeviceID", "type": "text" }]);
+    let schema = SchemaSeed(IdentifierCase::Lower).deserialize(json).unwrap();
+    assert_eq!(schema.fields[0].as_ref().unwrap().name, "deviceid");
+
+    let live = LiveSchemaSeed::new(IdentifierCase::Lower)
+        .deserialize(json!({ "DeviceID": "Tmp0233AO" }))
+        .unwrap();
+    assert_eq!(live.0[0].as_ref().unwrap().0.name, "deviceid");
+
+    // Lower is the policy of the Deserialize impl
+    let json = json!([{ "name": "DeviceID", "type": "text" }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(schema.fields[0].as_ref().unwrap().name, "deviceid");
+}
+
+#[test]
+fn identifier_case_reject_uppercase_policy() {
+    let json = json!([{ "name": "DeviceID", "type": "text" }]);
+    let err = SchemaSeed(IdentifierCase::RejectUppercase)
+        .deserialize(json)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        IdenError::Uppercase("DeviceID".into()).to_string()
+    );
+
+    let err = LiveSchemaSeed::new(IdentifierCase::RejectUppercase)
+        .deserialize(json!({ "DeviceID": "Tmp0233AO" }))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        IdenError::Uppercase("DeviceID".into()).to_string()
+    );
+
+    let json = json!([{ "name": "device_id", "type": "text" }]);
+    assert!(SchemaSeed(IdentifierCase::RejectUppercase)
+        .deserialize(json)
+        .is_ok());
+}
+
+#[test]
+fn identifier_case_preserve_policy() {
+    let json = json!([{ "name": "DeviceID", "type": "text" }]);
+    let schema = SchemaSeed(IdentifierCase::Preserve)
+        .deserialize(json)
+        .unwrap();
+    assert_eq!(schema.fields[0].as_ref().unwrap().name, "DeviceID");
+
+    let live = LiveSchemaSeed::new(IdentifierCase::Preserve)
+        .deserialize(json!({ "DeviceID": "Tmp0233AO" }))
+        .unwrap();
+    assert_eq!(live.0[0].as_ref().unwrap().0.name, "DeviceID");
+}
+
+#[test]
+fn names_only_differing_in_case_are_duplicates() {
+    for case in [IdentifierCase::Lower, IdentifierCase::Preserve] {
+        let json = json!([
+            { "name": "DeviceID", "type": "text" },
+            { "name": "deviceid", "type": "text" },
+        ]);
+        assert!(SchemaSeed(case).deserialize(json).is_err());
+
+        let json = json!({ "DeviceID": "Tmp0233AO", "deviceid": "Tmp0233AO" });
+        assert!(LiveSchemaSeed::new(case).deserialize(json).is_err());
+    }
+}
+
+#[test]
+fn wont_deserialize_live_schema_with_id_field() {
+    let err = serde_json::from_value::<LiveSchema>(json!({ "id": 1, "v": "a" })).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"field "id" collides with the synthetic id primary key, rename it"#
+    );
+
+    let json = json!({ "ID": 1, "v": "a" });
+    assert!(LiveSchemaSeed::new(IdentifierCase::Preserve)
+        .deserialize(json)
+        .is_err());
+}
+
+#[test]
+fn live_schema_with_nullable_fields() {
+    let json = json!({
+        "temperature": 23.2,
+        "device": "Tmp0233AO"
+    });
+    let schema = serde_json::from_value::<LiveSchema>(json).unwrap();
+
+    let sql = schema
+        .table_create_statement_with_nullable("test_t", &["temperature", "unknown"])
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""device" text,"#,
+        r#""temperature" real null,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+
+    assert_eq!(sql, table)
+}
+
+#[test]
+fn add_column_statement_from_field() {
+    let field = serde_json::from_value::<Field>(json!({ "name": "x", "type": "integer" })).unwrap();
+
+    let sql = field
+        .add_column_statement("t", SqlBackend::Postgres)
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert_eq!(sql, r#"alter table "t" add column "x" integer"#)
+}
+
+#[test]
+fn wont_deserialize_live_schema_with_numeric_keys() {
+    let json = json!({ "0": 1, "1": 2 });
+
+    let err = serde_json::from_value::<LiveSchema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "every key of the object is a number, it looks like an array sent as an object, \
+        send an object with named fields instead"
+    );
+
+    // A numeric key among named ones is left to identifier validation
+    assert!(serde_json::from_value::<LiveSchema>(json!({ "0": 1, "device": "x" })).is_ok());
+}
+
+#[test]
+fn numeric_columns_of_mixed_schema() {
+    let json = json!([
+        { "name": "temperature", "type": "float" },
+        { "name": "device", "type": "text" },
+        { "name": "count", "type": "integer" },
+        { "name": "active", "type": "bool" },
+        { "name": "price", "type": "decimal", "precision": 10, "scale": 2 },
+    ]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+
+    let numeric = schema
+        .columns_of_type(Type::is_numeric)
+        .into_iter()
+        .map(|field| field.name().as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(numeric, ["temperature", "count", "price"]);
+
+    let text = schema.columns_of_type(|t| *t == Type::Text);
+    assert_eq!(text.len(), 1);
+    assert_eq!(text[0].name(), "device");
+}
+
+#[test]
+fn json_schema_export_of_schema() {
+    let json = json!([
+        { "name": "temperature", "type": "float", "nullable": true },
+        { "name": "device", "type": "text", "length": 16 },
+        { "name": "price", "type": "decimal", "precision": 10, "scale": 2, "nullable": true },
+        { "name": "readings", "type": "array", "items": "integer", "nullable": true },
+        { "name": "payload", "type": "json", "nullable": true },
+    ]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+
+    let want = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "object",
+        "properties": {
+            "temperature": { "type": ["number", "null"] },
+            "device": { "type": "string", "maxLength": 16 },
+            "price": { "type": ["number", "string", "null"] },
+            "readings": { "type": ["array", "null"], "items": { "type": "integer" } },
+            "payload": {},
+        },
+        "required": ["device"],
+    });
+
+    assert_eq!(schema.to_json_schema(), want)
+}
+
+#[test]
+fn empty_schema_is_detectable() {
+    let schema = serde_json::from_value::<Schema>(json!([])).unwrap();
+    assert!(schema.is_empty());
+
+    let schema = serde_json::from_value::<Schema>(json!({ "fields": [] })).unwrap();
+    assert!(schema.is_empty());
+
+    let json = json!([{ "name": "device", "type": "text" }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert!(!schema.is_empty());
+}
+
+#[test]
+fn batch_insert_of_homogeneous_records() {
+    let records = json!([
+        { "temperature": 23.2, "device": "a" },
+        { "device": "b", "temperature": 19 },
+    ]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(records).unwrap();
+
+    let sql = LiveSchema::batch_insert_statement(&records, "t")
+        .unwrap()
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert_eq!(
+        sql,
+        r#"insert into "t" ("device", "temperature") values ('a', 23.2), ('b', 19)"#
+    )
+}
+
+#[test]
+fn batch_insert_rejects_divergent_records() {
+    let missing_field = json!([
+        { "temperature": 23.2, "device": "a" },
+        { "temperature": 19.1 },
+    ]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(missing_field).unwrap();
+    assert!(matches!(
+        LiveSchema::batch_insert_statement(&records, "t"),
+        Err(BatchError::DivergentFields { record: 1 })
+    ));
+
+    let other_field = json!([
+        { "temperature": 23.2, "device": "a" },
+        { "temperature": 19.1, "room": "b" },
+    ]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(other_field).unwrap();
+    assert!(matches!(
+        LiveSchema::batch_insert_statement(&records, "t"),
+        Err(BatchError::DivergentFields { record: 1 })
+    ));
+
+    let other_type = json!([
+        { "temperature": 23.2, "device": "a" },
+        { "temperature": "hot", "device": "b" },
+    ]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(other_type).unwrap();
+    assert!(matches!(
+        LiveSchema::batch_insert_statement(&records, "t"),
+        Err(BatchError::IncompatibleValue { record: 1, .. })
+    ));
+
+    assert!(matches!(
+        LiveSchema::batch_insert_statement(&[], "t"),
+        Err(BatchError::Empty)
+    ));
+
+    let no_fields = serde_json::from_value::<Vec<LiveSchema>>(json!([{}])).unwrap();
+    assert!(matches!(
+        LiveSchema::batch_insert_statement(&no_fields, "t"),
+        Err(BatchError::NoFields)
+    ));
+}
+
+#[test]
+fn nested_objects_and_arrays_are_inferred_as_json() {
+    let json = json!({
+        "location": { "room": "amber", "floor": 2 },
+        "readings": [{ "t": 1 }, { "t": 2 }],
+    });
+    let schema = serde_json::from_value::<LiveSchema>(json).unwrap();
+
+    for (field, _) in schema.0.iter().flatten() {
+        assert_eq!(field.field_type, Type::Json);
+    }
+
+    let sql = schema
+        .table_create_statement("test_t")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""location" jsonb,"#,
+        r#""readings" jsonb,"#,
+        r#""id" serial not null primary key"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(sql, table);
+
+    let (sql, values) = schema
+        .insert_statement("test_t")
+        .unwrap()
+        .build(PostgresQueryBuilder);
+    assert_eq!(
+        sql,
+        r#"INSERT INTO "test_t" ("location", "readings") VALUES ($1, $2)"#
+    );
+    assert_eq!(
+        values.0,
+        [
+            Value::from(r#"{"floor":2,"room":"amber"}"#),
+            Value::from(r#"[{"t":1},{"t":2}]"#)
+        ]
+    );
+}
+
+#[test]
+fn declared_id_field_is_rejected_by_default() {
+    let json = json!([
+        { "name": "id", "type": "text" },
+        { "name": "device", "type": "text" },
+    ]);
+
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"field "id" collides with the synthetic id primary key, rename it or set "id_field": "primary_key" to use it as the primary key"#
+    );
+
+    // With an explicit primary key there is no synthetic id to collide with
+    let json = json!({
+        "fields": [
+            { "name": "id", "type": "text" },
+            { "name": "device", "type": "text" },
+        ],
+        "primary_key": ["device"]
+    });
+    assert!(serde_json::from_value::<Schema>(json).is_ok());
+}
+
+#[test]
+fn declared_id_field_promoted_to_primary_key() {
+    let json = json!({
+        "fields": [
+            { "name": "id", "type": "text" },
+            { "name": "device", "type": "text" },
+        ],
+        "id_field": "primary_key"
+    });
+
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(schema.primary_key(), ["id"]);
+
+    let table = [
+        r#"create table "test_t" ("#,
+        r#""id" text,"#,
+        r#""device" text,"#,
+        r#"primary key ("id")"#,
+        r#")"#,
+    ]
+    .join(" ");
+    assert_eq!(
+        schema.to_sql("test_t", SqlBackend::Postgres).to_lowercase(),
+        table
+    );
+}
+
+#[test]
+fn sql_type_names_per_backend() {
+    let types = [
+        (Type::SmallInt, "smallint", "smallint", "integer"),
+        (Type::Integer, "integer", "int", "integer"),
+        (Type::BigInt, "bigint", "bigint", "bigint"),
+        (Type::Float, "real", "float", "real"),
+        (Type::Text, "text", "text", "text"),
+        (Type::Bool, "bool", "bool", "boolean"),
+        (
+            Type::Decimal {
+                precision: 10,
+                scale: 2,
+            },
+            "decimal(10, 2)",
+            "decimal(10, 2)",
+            "real(10, 2)",
+        ),
+        (Type::Uuid, "uuid", "char(36)", "text(36)"),
+        (Type::Interval, "interval", "text", "text"),
+        (Type::Json, "jsonb", "json", "text"),
+    ];
+
+    for (field_type, postgres, mysql, sqlite) in types {
+        assert_eq!(field_type.sql_type_name(SqlBackend::Postgres), postgres);
+        assert_eq!(field_type.sql_type_name(SqlBackend::MySql), mysql);
+        assert_eq!(field_type.sql_type_name(SqlBackend::Sqlite), sqlite);
+
+        // The names match the columns of the generated statements
+        let sql = field("c", field_type, false)
+            .add_column_statement("t", SqlBackend::Postgres)
+            .to_string(PostgresQueryBuilder);
+        assert_eq!(sql, format!(r#"ALTER TABLE "t" ADD COLUMN "c" {postgres}"#));
+    }
+}
+
+#[test]
+fn batch_upsert_conflict_strategies() {
+    let schema = serde_json::from_value::<Schema>(json!({
+        "fields": [
+            { "name": "device", "type": "text" },
+            { "name": "temperature", "type": "float", "nullable": true },
+            { "name": "room", "type": "text", "nullable": true },
+        ],
+        "primary_key": ["device"]
+    }))
+    .unwrap();
+    let records = json!([
+        { "device": "a", "temperature": 23.2 },
+        { "device": "a", "temperature": 19 },
+    ]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(records).unwrap();
+
+    let insert = r#"insert into "t" ("device", "temperature") values ('a', 23.2), ('a', 19)"#;
+    let upsert = |strategy| {
+        LiveSchema::batch_upsert_statement(&records, "t", &schema, strategy)
+            .unwrap()
+            .to_string(PostgresQueryBuilder)
+            .to_lowercase()
+    };
+
+    assert_eq!(upsert(ConflictStrategy::Error), insert);
+    assert_eq!(
+        upsert(ConflictStrategy::Ignore),
+        format!(r#"{insert} on conflict ("device") do nothing"#)
+    );
+    assert_eq!(
+        upsert(ConflictStrategy::Merge),
+        format!(
+            r#"{insert} on conflict ("device") do update set "temperature" = "excluded"."temperature""#
+        )
+    );
+    assert_eq!(
+        upsert(ConflictStrategy::Replace),
+        format!(
+            r#"{insert} on conflict ("device") do update set "temperature" = "excluded"."temperature", "room" = null"#
+        )
+    );
+
+    // MySQL has no DO NOTHING, skipped records assign a key column to itself
+    let mysql = |strategy| {
+        LiveSchema::batch_upsert_statement_for(&records, "t", &schema, strategy, SqlBackend::MySql)
+            .unwrap()
+            .to_string(MysqlQueryBuilder)
+            .to_lowercase()
+    };
+    let insert = "insert into `t` (`device`, `temperature`) values ('a', 23.2), ('a', 19)";
+    assert_eq!(
+        mysql(ConflictStrategy::Ignore),
+        format!("{insert} on duplicate key update `device` = `device`")
+    );
+    assert_eq!(
+        mysql(ConflictStrategy::Merge),
+        format!("{insert} on duplicate key update `temperature` = values(`temperature`)")
+    );
+
+    // Records holding only the primary key still null the columns they leave out
+    let schema = serde_json::from_value::<Schema>(json!({
+        "fields": [
+            { "name": "a", "type": "integer" },
+            { "name": "b", "type": "text", "nullable": true },
+        ],
+        "primary_key": ["a"]
+    }))
+    .unwrap();
+    let records = serde_json::from_value::<Vec<LiveSchema>>(json!([{ "a": 1 }])).unwrap();
+    let upsert = |strategy| {
+        LiveSchema::batch_upsert_statement(&records, "t", &schema, strategy)
+            .unwrap()
+            .to_string(PostgresQueryBuilder)
+            .to_lowercase()
+    };
+
+    let insert = r#"insert into "t" ("a") values (1)"#;
+    assert_eq!(
+        upsert(ConflictStrategy::Merge),
+        format!(r#"{insert} on conflict ("a") do nothing"#)
+    );
+    assert_eq!(
+        upsert(ConflictStrategy::Replace),
+        format!(r#"{insert} on conflict ("a") do update set "b" = null"#)
+    );
+}
+
+#[test]
+fn batch_upsert_needs_a_usable_conflict_target() {
+    let records = json!([{ "device": "a", "temperature": 23.2 }]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(records).unwrap();
+
+    // Only the synthetic id, which the records never carry
+    let schema = serde_json::from_value::<Schema>(json!([
+        { "name": "device", "type": "text" },
+        { "name": "temperature", "type": "float" },
+    ]))
+    .unwrap();
+    assert!(matches!(
+        LiveSchema::batch_upsert_statement(&records, "t", &schema, ConflictStrategy::Ignore),
+        Err(BatchError::NoConflictTarget)
+    ));
+    assert!(
+        LiveSchema::batch_upsert_statement(&records, "t", &schema, ConflictStrategy::Error).is_ok()
+    );
+
+    // A unique index the records do not provide every column of
+    let schema = serde_json::from_value::<Schema>(json!({
+        "fields": [
+            { "name": "device", "type": "text" },
+            { "name": "room", "type": "text" },
+            { "name": "temperature", "type": "float" },
+        ],
+        "indexes": [{ "name": "device_room", "columns": ["device", "room"], "unique": true }]
+    }))
+    .unwrap();
+    assert_eq!(
+        schema.conflict_target(),
+        Some(["device".to_string(), "room".to_string()].as_slice())
+    );
+    assert!(matches!(
+        LiveSchema::batch_upsert_statement(&records, "t", &schema, ConflictStrategy::Merge),
+        Err(BatchError::MissingConflictColumn { column }) if column == "room"
+    ));
+}
+
+#[test]
+fn declared_bigint_column() {
+    let json = json!([
+        { "name": "events", "type": "bigint" },
+        { "name": "sequence", "type": "bigint", "auto_increment": true },
+    ]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("counters")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert_eq!(
+        sql,
+        r#"create table "counters" ( "events" bigint, "sequence" bigserial, "id" serial not null primary key )"#
+    );
+}
+
+#[test]
+fn integers_beyond_32_bits_are_inferred_as_bigint() {
+    let json = json!({
+        "a": i32::MAX,
+        "b": i32::MAX as i64 + 1,
+        "c": -(i32::MAX as i64),
+        "d": i32::MIN,
+        "e": 9_000_000_000_i64,
+    });
+    let schema = serde_json::from_value::<LiveSchema>(json).unwrap();
+    let types = schema
+        .ordered_fields()
+        .into_iter()
+        .map(|field| field.field_type().clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        [
+            Type::Integer,
+            Type::BigInt,
+            Type::Integer,
+            Type::BigInt,
+            Type::BigInt
+        ]
+    );
+    assert_eq!(
+        schema
+            .insert_statement("t")
+            .unwrap()
+            .to_string(PostgresQueryBuilder),
+        r#"INSERT INTO "t" ("a", "b", "c", "d", "e") VALUES (2147483647, 2147483648, -2147483647, -2147483648, 9000000000)"#
+    );
+}
+
+#[test]
+fn live_schema_rejects_deeply_nested_json() {
+    let nest = |depth: usize| {
+        (0..depth).fold(json!(1), |value, level| match level % 2 {
+            0 => json!([value]),
+            _ => json!({ "inner": value }),
+        })
+    };
+
+    let live = serde_json::from_value::<LiveSchema>(json!({ "doc": nest(MAX_JSON_DEPTH) }));
+    assert!(live.is_ok());
+
+    let err = serde_json::from_value::<LiveSchema>(json!({ "doc": nest(MAX_JSON_DEPTH + 1) }))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(r#"field "doc" nests its JSON value deeper than {MAX_JSON_DEPTH} levels"#)
+    );
+
+    let seed = LiveSchemaSeed {
+        case: IdentifierCase::Lower,
+        max_json_depth: 2,
+    };
+    assert!(seed.deserialize(json!({ "doc": nest(3) })).is_err());
+}
+
+#[test]
+fn field_references_become_foreign_keys() {
+    let json = json!([
+        {
+            "name": "device_id",
+            "type": "integer",
+            "references": { "table": "devices", "column": "id" }
+        },
+        { "name": "temperature", "type": "float" },
+    ]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("readings")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert!(
+        sql.contains(r#"foreign key ("device_id") references "devices" ("id")"#),
+        "{sql}"
+    );
+}
+
+#[test]
+fn wont_deserialize_reference_to_invalid_table() {
+    for table in ["", "devices; drop table readings", "Devices"] {
+        let json = json!([{
+            "name": "device_id",
+            "type": "integer",
+            "references": { "table": table, "column": "id" }
+        }]);
+        assert!(serde_json::from_value::<Schema>(json).is_err());
+    }
+}
+
+#[test]
+fn timestamps_add_audit_columns() {
+    let fields = json!([{ "name": "device", "type": "text" }]);
+    let sql = |timestamps: bool| {
+        serde_json::from_value::<Schema>(json!({ "fields": fields, "timestamps": timestamps }))
+            .unwrap()
+            .table_create_statement("readings")
+            .to_string(PostgresQueryBuilder)
+            .to_lowercase()
+    };
+
+    assert_eq!(
+        sql(true),
+        [
+            r#"create table "readings" ( "device" text,"#,
+            r#""created_at" timestamp not null default current_timestamp,"#,
+            r#""updated_at" timestamp not null default current_timestamp,"#,
+            r#""id" serial not null primary key )"#,
+        ]
+        .join(" ")
+    );
+    assert_eq!(
+        sql(false),
+        r#"create table "readings" ( "device" text, "id" serial not null primary key )"#
+    );
+}
+
+#[test]
+fn wont_deserialize_field_colliding_with_timestamps() {
+    let json = json!({
+        "fields": [{ "name": "created_at", "type": "text" }],
+        "timestamps": true
+    });
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"field "created_at" collides with the timestamp columns, rename it or set "timestamps": false"#
+    );
+
+    // Without the option the name is free to use
+    let json = json!({ "fields": [{ "name": "created_at", "type": "text" }] });
+    assert!(serde_json::from_value::<Schema>(json).is_ok());
+}
+
+#[test]
+fn schema_diff_statements() {
+    let current = serde_json::from_value::<Schema>(json!([
+        { "name": "device", "type": "text" },
+        { "name": "temperature", "type": "integer" },
+        { "name": "room", "type": "text" },
+    ]))
+    .unwrap();
+    let next = serde_json::from_value::<Schema>(json!([
+        { "name": "device", "type": "text" },
+        { "name": "temperature", "type": "float" },
+        { "name": "humidity", "type": "float", "nullable": true },
+    ]))
+    .unwrap();
+
+    let statements = current
+        .diff(&next, "readings")
+        .unwrap()
+        .iter()
+        .map(|statement| statement.to_string(PostgresQueryBuilder).to_lowercase())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        statements,
+        [
+            r#"alter table "readings" drop column "room""#,
+            r#"alter table "readings" alter column "temperature" type real"#,
+            r#"alter table "readings" add column "humidity" real null"#,
+        ]
+    );
+
+    assert!(next.diff(&next, "readings").unwrap().is_empty());
+
+    // SQLite can't alter the type of the temperature column
+    assert_eq!(
+        current
+            .diff_for(&next, "readings", SqlBackend::Sqlite)
+            .unwrap_err(),
+        SchemaDiffError::UnsupportedModify("temperature".into())
+    );
+}
+
+#[test]
+fn schema_diff_leaves_constraints_out_of_modified_columns() {
+    let current = serde_json::from_value::<Schema>(json!([
+        { "name": "device", "type": "text", "unique": true, "non_empty": true },
+        { "name": "level", "type": "integer", "check": "level > 0" },
+        { "name": "room", "type": "text", "nullable": true },
+        { "name": "humidity", "type": "integer", "nullable": true },
+    ]))
+    .unwrap();
+    let next = serde_json::from_value::<Schema>(json!([
+        { "name": "device", "type": "text", "unique": true, "non_empty": true, "nullable": true },
+        { "name": "level", "type": "bigint", "check": "level > 0" },
+        { "name": "room", "type": "text" },
+        { "name": "humidity", "type": "float", "nullable": true },
+    ]))
+    .unwrap();
+
+    let statements = current
+        .diff(&next, "readings")
+        .unwrap()
+        .iter()
+        .map(|statement| statement.to_string(PostgresQueryBuilder).to_lowercase())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        statements,
+        [
+            r#"alter table "readings" alter column "device" type text, alter column "device" drop not null"#,
+            r#"alter table "readings" alter column "level" type bigint"#,
+            r#"alter table "readings" alter column "humidity" type real"#,
+        ]
+    );
+}
+
+#[test]
+fn non_empty_text_check_constraint() {
+    let json = json!([
+        { "name": "device", "type": "text", "non_empty": true },
+        { "name": "room", "type": "text", "length": 32, "non_empty": true },
+    ]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("readings")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert_eq!(
+        sql,
+        [
+            r#"create table "readings" ("#,
+            r#""device" text check (length(trim("device")) > 0),"#,
+            r#""room" varchar(32) check (length(trim("room")) > 0),"#,
+            r#""id" serial not null primary key )"#,
+        ]
+        .join(" ")
+    );
+
+    let mysql = schema.to_sql("readings", SqlBackend::MySql).to_lowercase();
+    assert!(
+        mysql.contains("`device` text check (length(trim(`device`)) > 0)"),
+        "{mysql}"
+    );
+
+    let sqlite = schema.to_sql("readings", SqlBackend::Sqlite).to_lowercase();
+    assert!(
+        sqlite.contains(r#""device" text check (length(trim("device")) > 0)"#),
+        "{sqlite}"
+    );
+    assert!(!sqlite.contains("$1"), "{sqlite}");
+}
+
+#[test]
+fn wont_deserialize_non_empty_on_non_text() {
+    let json = json!([{ "name": "temperature", "type": "float", "non_empty": true }]);
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"non_empty field "temperature" must be text"#
+    );
+}
+
+#[test]
+fn wont_infer_numbers_beyond_exact_range() {
+    for json in [
+        r#"{ "big": 123456789012345678901234567890 }"#,
+        r#"{ "big": 18446744073709551615 }"#,
+        r#"{ "big": 9007199254740992.0 }"#,
+    ] {
+        let err = serde_json::from_str::<LiveSchema>(json).unwrap_err();
+        assert!(
+            err.to_string().contains("too large to be stored exactly"),
+            "{err}"
+        );
+    }
+
+    // Fractions and integers within range are kept as they are
+    let json = r#"{ "a": 9007199254740991.0, "b": -1.5e15, "c": 9223372036854775807 }"#;
+    let schema = serde_json::from_str::<LiveSchema>(json).unwrap();
+    let types = schema
+        .ordered_fields()
+        .into_iter()
+        .map(|field| field.field_type().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(types, [Type::Float, Type::Float, Type::BigInt]);
+}
+
+#[test]
+fn standalone_types_are_written_by_name() {
+    assert_eq!(
+        serde_json::to_value(Type::Integer).unwrap(),
+        json!("integer")
+    );
+    assert_eq!(
+        serde_json::from_value::<Type>(json!("integer")).unwrap(),
+        Type::Integer
+    );
+
+    let decimal = Type::Decimal {
+        precision: 10,
+        scale: 2,
+    };
+    let serialized = serde_json::to_value(&decimal).unwrap();
+    assert_eq!(
+        serialized,
+        json!({ "decimal": { "precision": 10, "scale": 2 } })
+    );
+    assert_eq!(serde_json::from_value::<Type>(serialized).unwrap(), decimal);
+}
+
+#[test]
+fn bool_type_round_trip() {
+    let serialized = serde_json::to_value(Type::Bool).unwrap();
+    assert_eq!(serialized, json!("bool"));
+    assert_eq!(
+        serde_json::from_value::<Type>(serialized).unwrap(),
+        Type::Bool
+    );
+    assert_eq!(
+        serde_json::from_value::<Type>(json!("boolean")).unwrap(),
+        Type::Bool
+    );
+
+    // Both spellings, declared or inferred, end up as the same column type
+    for spelling in ["bool", "boolean"] {
+        let schema =
+            serde_json::from_value::<Schema>(json!([{ "name": "active", "type": spelling }]))
+                .unwrap();
+        assert_eq!(
+            schema
+                .table_create_statement("t")
+                .to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "t" ( "active" bool, "id" serial NOT NULL PRIMARY KEY )"#
+        );
+    }
+    let live = serde_json::from_value::<LiveSchema>(json!({ "active": true })).unwrap();
+    assert_eq!(
+        live.table_create_statement("t")
+            .to_string(PostgresQueryBuilder),
+        r#"CREATE TABLE "t" ( "active" bool, "id" serial NOT NULL PRIMARY KEY )"#
+    );
+}
+
+#[test]
+fn field_check_constraint() {
+    let json = json!([{
+        "name": "temperature",
+        "type": "float",
+        "check": "temperature between -50 and 150"
+    }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("readings")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+
+    assert_eq!(
+        sql,
+        r#"create table "readings" ( "temperature" real check (temperature between -50 and 150), "id" serial not null primary key )"#
+    );
+}
+
+#[test]
+fn wont_deserialize_unsafe_check_expressions() {
+    for check in [
+        "",
+        "  ",
+        "true); drop table readings; --",
+        "temperature > 0) or (true",
+        "(temperature > 0",
+        "temperature > 0 -- and temperature < 100",
+        "temperature > 0 /* and temperature < 100 */",
+        // A parenthesis in a literal closing the clause, adding a column
+        r#"x <> '(' ) , "evil" integer, check (x <> ')'"#,
+        "x <> 'unterminated",
+        r#"x <> 'it\'s'"#,
+        "x <> $$)$$",
+        "temperature > 0 # and temperature < 100",
+    ] {
+        let json = json!([{ "name": "temperature", "type": "float", "check": check }]);
+        let err = serde_json::from_value::<Schema>(json).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "check of field \"temperature\" must be a non-empty expression with balanced \
+            parentheses and quotes, without semicolons, comments, backslashes or dollar signs"
+        );
+    }
+
+    for check in [
+        "(temperature > 0)",
+        "status in ('on', 'off')",
+        "label <> ')' and label <> 'it''s -- fine; (really'",
+    ] {
+        let json = json!([{ "name": "temperature", "type": "float", "check": check }]);
+        assert!(serde_json::from_value::<Schema>(json).is_ok(), "{check}");
+    }
+}
+
+#[test]
+fn custom_synthetic_id_column_name() {
+    let json = json!({
+        "fields": [
+            { "name": "id", "type": "text" },
+            { "name": "temperature", "type": "float" },
+        ],
+        "id_column": "reading_id"
+    });
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(schema.id_column(), "reading_id");
+
+    let sql = schema
+        .table_create_statement("readings")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+    assert_eq!(
+        sql,
+        r#"create table "readings" ( "id" text, "temperature" real, "reading_id" serial not null primary key )"#
+    );
+}
+
+#[test]
+fn wont_deserialize_field_colliding_with_id_column() {
+    let json = json!({
+        "fields": [{ "name": "reading_id", "type": "integer" }],
+        "id_column": "reading_id"
+    });
+    let err = serde_json::from_value::<Schema>(json).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with(r#"field "reading_id" collides"#));
+
+    let json = json!({
+        "fields": [{ "name": "temperature", "type": "float" }],
+        "id_column": "reading id"
+    });
+    assert!(serde_json::from_value::<Schema>(json).is_err());
+}
+
+#[test]
+fn validate_constructed_schemas() {
+    let schema = |fields: Vec<Field>| Schema {
+        fields: fields.into_iter().map(Some).collect(),
+        ..Default::default()
+    };
+
+    let valid = schema(vec![
+        field("device", Type::Text, false),
+        field("Temperature", Type::Float, false),
+    ]);
+    assert_eq!(valid.validate(), Ok(()));
+
+    assert_eq!(schema(vec![]).validate(), Err(SchemaValidationError::Empty));
+
+    let duplicate = schema(vec![
+        field("device", Type::Text, false),
+        field("Device", Type::Text, false),
+    ]);
+    assert_eq!(
+        duplicate.validate(),
+        Err(SchemaValidationError::DuplicateField("Device".into()))
+    );
+
+    // Names that would only differ once Postgres truncates them are too long
+    let prefix = "a".repeat(MAX_IDEN_LEN);
+    let truncated = schema(vec![
+        field(&format!("{prefix}_first"), Type::Text, false),
+        field(&format!("{prefix}_second"), Type::Text, false),
+    ]);
+    assert_eq!(
+        truncated.validate(),
+        Err(SchemaValidationError::InvalidIdentifier {
+            name: format!("{prefix}_first"),
+            source: IdenError::TooLong(MAX_IDEN_LEN + 6),
+        })
+    );
+    assert_eq!(
+        schema(vec![field(&prefix, Type::Text, false)]).validate(),
+        Ok(())
+    );
+    let json = json!([{ "name": "A".repeat(MAX_IDEN_LEN + 1), "type": "text" }]);
+    assert!(serde_json::from_value::<Schema>(json).is_err());
+
+    // Column names are always quoted, so any name the deserializer accepts is valid
+    let quoted = schema(vec![field("temp-c", Type::Float, false)]);
+    assert_eq!(quoted.validate(), Ok(()));
+    assert!(
+        serde_json::from_value::<Schema>(json!([{ "name": "temp-c", "type": "float" }])).is_ok()
+    );
+
+    let id = schema(vec![field("ID", Type::Integer, false)]);
+    assert_eq!(
+        id.validate(),
+        Err(SchemaValidationError::IdFieldCollision("ID".into()))
+    );
+
+    let timestamps = Schema {
+        timestamps: true,
+        ..schema(vec![field("created_at", Type::Text, false)])
+    };
+    assert_eq!(
+        timestamps.validate(),
+        Err(SchemaValidationError::TimestampCollision(
+            "created_at".into()
+        ))
+    );
+
+    let too_many = schema(
+        (0..=MAX_FIELDS)
+            .map(|i| field(&format!("f{i}"), Type::Text, false))
+            .collect(),
+    );
+    assert_eq!(
+        too_many.validate(),
+        Err(SchemaValidationError::TooManyFields(MAX_FIELDS + 1))
+    );
+
+    let unknown_key = Schema {
+        primary_key: vec!["room".into()],
+        ..schema(vec![field("device", Type::Text, false)])
+    };
+    assert_eq!(
+        unknown_key.validate(),
+        Err(SchemaValidationError::UnknownPrimaryKeyColumn(
+            "room".into()
+        ))
+    );
+
+    let index = serde_json::from_value(json!({ "name": "room_idx", "columns": ["room"] }));
+    let unknown_index = Schema {
+        indexes: vec![index.unwrap()],
+        ..schema(vec![field("device", Type::Text, false)])
+    };
+    assert_eq!(
+        unknown_index.validate(),
+        Err(SchemaValidationError::UnknownIndexColumn {
+            index: "room_idx".into(),
+            column: "room".into(),
+        })
+    );
+
+    let empty_name = schema(vec![field("", Type::Text, false)]);
+    assert!(matches!(
+        empty_name.validate(),
+        Err(SchemaValidationError::InvalidIdentifier {
+            source: IdenError::Empty,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn declared_array_columns() {
+    let json = json!([
+        { "name": "readings", "type": "array", "items": "integer" },
+        { "name": "tags", "type": "array", "items": { "type": "text" } },
+    ]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(
+        schema.inner()[0].as_ref().unwrap().field_type(),
+        &Type::Array {
+            items: Box::new(Type::Integer)
+        }
+    );
+
+    let sql = schema
+        .table_create_statement("t")
+        .to_string(PostgresQueryBuilder);
+    assert_eq!(
+        sql,
+        r#"CREATE TABLE "t" ( "readings" integer[], "tags" text[], "id" serial NOT NULL PRIMARY KEY )"#
+    );
+
+    // Element types without parameters are written by their name
+    assert_eq!(
+        serde_json::to_value(schema.inner()[0].as_ref().unwrap()).unwrap(),
+        json!({
+            "name": "readings",
+            "type": "array",
+            "items": "integer",
+            "nullable": false,
+            "ordinal": null,
+            "length": null,
+            "auto_increment": false,
+            "references": null,
+            "non_empty": false,
+            "check": null,
+            "unique": false,
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(schema.inner()[0].as_ref().unwrap().field_type()).unwrap(),
+        json!({ "array": { "items": "integer" } })
+    );
+
+    // Backends without arrays store them as JSON
+    assert_eq!(
+        Type::Array {
+            items: Box::new(Type::Text)
+        }
+        .sql_type_name(SqlBackend::MySql),
+        "json"
+    );
+
+    let nested =
+        json!([{ "name": "m", "type": "array", "items": { "type": "array", "items": "integer" } }]);
+    assert!(serde_json::from_value::<Schema>(nested).is_err());
+}
+
+#[test]
+fn declared_json_arrays_are_inserted_as_array_literals() {
+    let json = json!([{ "name": "events", "type": "array", "items": "json" }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    let sql = schema
+        .table_create_statement("t")
+        .to_string(PostgresQueryBuilder);
+    assert!(sql.contains(r#""events" jsonb[]"#), "{sql}");
+
+    // Each document is an element of the literal, which the jsonb[] column parses
+    let events = schema.inner()[0].as_ref().unwrap();
+    assert_eq!(
+        events
+            .sql_value(&json!([{ "kind": "start" }, [1, "two"]]))
+            .unwrap(),
+        Value::from(r#"{"{\"kind\":\"start\"}","[1,\"two\"]"}"#)
+    );
+    assert!(events.sql_value(&json!({ "kind": "start" })).is_err());
+}
+
+#[test]
+fn homogeneous_arrays_are_inferred_as_array_columns() {
+    let json = json!({
+        "readings": [1, 2, 3],
+        "tags": ["hot", "humid"],
+    });
+    let schema = serde_json::from_value::<LiveSchema>(json).unwrap();
+    let types = schema
+        .ordered_fields()
+        .into_iter()
+        .map(|field| field.field_type().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        [
+            Type::Array {
+                items: Box::new(Type::Integer)
+            },
+            Type::Array {
+                items: Box::new(Type::Text)
+            },
+        ]
+    );
+
+    assert_eq!(
+        schema
+            .insert_statement("t")
+            .unwrap()
+            .to_string(PostgresQueryBuilder),
+        r#"INSERT INTO "t" ("readings", "tags") VALUES (ARRAY [1,2,3], ARRAY ['hot','humid'])"#
+    );
+
+    // Integers and floats widen to floats
+    let schema = serde_json::from_value::<LiveSchema>(json!({ "r": [1, 2.5] })).unwrap();
+    assert_eq!(
+        schema.ordered_fields()[0].field_type(),
+        &Type::Array {
+            items: Box::new(Type::Float)
+        }
+    );
+}
+
+#[test]
+fn inferred_uuid_arrays_are_inserted_as_array_literals() {
+    let a = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let b = "0f1e2d3c-4b5a-4697-8877-665544332211";
+    let schema = serde_json::from_value::<LiveSchema>(json!({ "devices": [a, b] })).unwrap();
+
+    let create = schema
+        .table_create_statement("t")
+        .to_string(PostgresQueryBuilder);
+    assert!(create.contains(r#""devices" uuid[]"#), "{create}");
+
+    // A text array could not be stored in the uuid[] column, the literal is parsed by it.
+    // The builder escapes its double quotes in an E'' string
+    assert_eq!(
+        schema
+            .insert_statement("t")
+            .unwrap()
+            .to_string(PostgresQueryBuilder),
+        format!(r#"INSERT INTO "t" ("devices") VALUES (E'{{\"{a}\",\"{b}\"}}')"#)
+    );
+}
+
+#[test]
+fn wont_infer_mixed_arrays() {
+    let err =
+        serde_json::from_value::<LiveSchema>(json!({ "readings": [1, "two", 3] })).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The array mixes Integer and Text elements, arrays can only hold one type"
+    );
+}
+
+#[test]
+fn schema_builder_two_fields() {
+    let schema = SchemaBuilder::new()
+        .field("device", Type::Text)
+        .unique()
+        .field("temperature", Type::Float)
+        .nullable()
+        .build()
+        .unwrap();
+
+    let sql = schema
+        .table_create_statement("readings")
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+    assert_eq!(
+        sql,
+        r#"create table "readings" ( "device" text unique, "temperature" real null, "id" serial not null primary key )"#
+    );
+}
+
+#[test]
+fn schema_builder_rejects_invalid_schemas() {
+    let duplicate = SchemaBuilder::new()
+        .field("device", Type::Text)
+        .field("device", Type::Integer)
+        .build();
+    assert_eq!(
+        duplicate.unwrap_err(),
+        SchemaValidationError::DuplicateField("device".into())
+    );
+
+    assert_eq!(
+        SchemaBuilder::new().nullable().build().unwrap_err(),
+        SchemaValidationError::Empty
+    );
+
+    // The synthetic id column would be created twice
+    assert_eq!(
+        SchemaBuilder::new()
+            .field("id", Type::Integer)
+            .build()
+            .unwrap_err(),
+        SchemaValidationError::IdFieldCollision("id".into())
+    );
+}
+
+#[test]
+fn unique_fields_are_a_conflict_target() {
+    let schema = SchemaBuilder::new()
+        .field("temperature", Type::Float)
+        .field("device", Type::Text)
+        .unique()
+        .build()
+        .unwrap();
+    assert_eq!(
+        schema.conflict_target(),
+        Some(["device".to_string()].as_slice())
+    );
+
+    let records = json!([{ "device": "a", "temperature": 23.2 }]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(records).unwrap();
+    let sql = LiveSchema::batch_upsert_statement(&records, "t", &schema, ConflictStrategy::Merge)
+        .unwrap()
+        .to_string(PostgresQueryBuilder)
+        .to_lowercase();
+    assert!(
+        sql.ends_with(
+            r#"on conflict ("device") do update set "temperature" = "excluded"."temperature""#
+        ),
+        "{sql}"
+    );
+}
+
+#[test]
+fn reserved_words_are_always_quoted() {
+    let json = json!({
+        "fields": [
+            { "name": "order", "type": "integer" },
+            { "name": "select", "type": "text" },
+            { "name": r#"from"table"#, "type": "text" },
+        ],
+        "indexes": [{ "name": "group", "columns": ["order"] }]
+    });
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+
+    let (table, indexes) = schema.create_statements("user");
+    assert_eq!(
+        table.to_string(PostgresQueryBuilder),
+        r#"CREATE TABLE "user" ( "order" integer, "select" text, "from""table" text, "id" serial NOT NULL PRIMARY KEY )"#
+    );
+    assert_eq!(
+        indexes[0].to_string(PostgresQueryBuilder),
+        r#"CREATE INDEX "group" ON "user" ("order")"#
+    );
+
+    let (table, _) = schema.create_statements_for("user", SqlBackend::MySql);
+    assert!(table
+        .to_string(MysqlQueryBuilder)
+        .starts_with("CREATE TABLE `user` ( `order` int, `select` text"));
+
+    let live = serde_json::from_value::<LiveSchema>(json!({ "order": 1 })).unwrap();
+    assert_eq!(
+        live.insert_statement("user")
+            .unwrap()
+            .to_string(PostgresQueryBuilder),
+        r#"INSERT INTO "user" ("order") VALUES (1)"#
+    );
+}
+
+#[test]
+fn smallint_columns_and_integer_ranges() {
+    let json = json!([{ "name": "level", "type": "smallint" }]);
+    let schema = serde_json::from_value::<Schema>(json).unwrap();
+    assert_eq!(
+        schema
+            .table_create_statement("t")
+            .to_string(PostgresQueryBuilder),
+        r#"CREATE TABLE "t" ( "level" smallint, "id" serial NOT NULL PRIMARY KEY )"#
+    );
+
+    let level = field("level", Type::SmallInt, false);
+    assert_eq!(
+        level.sql_value(&json!(32767)).unwrap(),
+        Value::from(32767_i64)
+    );
+
+    let err = level.sql_value(&json!(32768)).unwrap_err();
+    assert!(matches!(
+        err,
+        TypeErrors::IntegerOverflow(_, Type::SmallInt)
+    ));
+    assert_eq!(
+        err.to_string(),
+        "The value 32768 is out of the range of a SmallInt column"
+    );
+
+    let count = field("count", Type::Integer, false);
+    assert!(count.sql_value(&json!(i32::MIN)).is_ok());
+    assert!(matches!(
+        count.sql_value(&json!(i32::MAX as i64 + 1)),
+        Err(TypeErrors::IntegerOverflow(_, Type::Integer))
+    ));
+
+    // A batch whose later records outgrow the columns of the first one
+    let records = json!([{ "level": 1 }, { "level": 3_000_000_000_i64 }]);
+    let records = serde_json::from_value::<Vec<LiveSchema>>(records).unwrap();
+    assert!(matches!(
+        LiveSchema::batch_insert_statement(&records, "t"),
+        Err(BatchError::IncompatibleValue {
+            record: 1,
+            source: TypeErrors::IntegerOverflow(..)
+        })
+    ));
+}